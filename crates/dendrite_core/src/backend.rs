@@ -0,0 +1,145 @@
+use std::fmt;
+
+use crate::storage::GrowthProfile;
+
+/// Errors raised while saving or loading a profile
+#[derive(Debug)]
+pub enum StorageError {
+    /// No profile has been saved yet
+    NotFound,
+    Io(std::io::Error),
+    Serialization(serde_json::Error),
+}
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StorageError::NotFound => write!(f, "no saved profile found"),
+            StorageError::Io(e) => write!(f, "storage I/O error: {}", e),
+            StorageError::Serialization(e) => write!(f, "profile serialization error: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for StorageError {}
+
+impl From<std::io::Error> for StorageError {
+    fn from(e: std::io::Error) -> Self {
+        StorageError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for StorageError {
+    fn from(e: serde_json::Error) -> Self {
+        StorageError::Serialization(e)
+    }
+}
+
+/// Somewhere a profile can be persisted, for native (non-WASM) consumers.
+/// The WASM bindings keep exchanging profiles as JSON strings instead.
+pub trait StorageBackend {
+    fn save(&mut self, profile: &GrowthProfile) -> Result<(), StorageError>;
+    fn load(&self) -> Result<GrowthProfile, StorageError>;
+}
+
+/// Keeps the serialized profile in memory
+#[derive(Debug, Default)]
+pub struct MemoryBackend {
+    json: Option<String>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl StorageBackend for MemoryBackend {
+    fn save(&mut self, profile: &GrowthProfile) -> Result<(), StorageError> {
+        self.json = Some(profile.to_json()?);
+        Ok(())
+    }
+
+    fn load(&self) -> Result<GrowthProfile, StorageError> {
+        let json = self.json.as_ref().ok_or(StorageError::NotFound)?;
+        Ok(GrowthProfile::from_json(json)?)
+    }
+}
+
+/// Stores the profile as a JSON file on disk
+#[cfg(feature = "fs")]
+#[derive(Debug, Clone)]
+pub struct FileBackend {
+    path: std::path::PathBuf,
+}
+
+#[cfg(feature = "fs")]
+impl FileBackend {
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+#[cfg(feature = "fs")]
+impl StorageBackend for FileBackend {
+    fn save(&mut self, profile: &GrowthProfile) -> Result<(), StorageError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // Write to a sibling file first so a crash never leaves a truncated profile
+        let tmp_path = self.path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, profile.to_json()?)?;
+        std::fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+
+    fn load(&self) -> Result<GrowthProfile, StorageError> {
+        let json = match std::fs::read_to_string(&self.path) {
+            Ok(json) => json,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(StorageError::NotFound)
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Ok(GrowthProfile::from_json(&json)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::session::Session;
+
+    #[test]
+    fn test_memory_backend_round_trip() {
+        let mut backend = MemoryBackend::new();
+        assert!(matches!(backend.load(), Err(StorageError::NotFound)));
+
+        let mut profile = GrowthProfile::new();
+        profile.add_session(Session::new(1));
+        backend.save(&profile).unwrap();
+
+        let loaded = backend.load().unwrap();
+        assert_eq!(loaded.id, profile.id);
+        assert_eq!(loaded.sessions.len(), 1);
+    }
+
+    #[cfg(feature = "fs")]
+    #[test]
+    fn test_file_backend_round_trip() {
+        let dir = std::env::temp_dir().join(format!("dendrite-backend-{}", uuid::Uuid::new_v4()));
+        let mut backend = FileBackend::new(dir.join("profile.json"));
+        assert!(matches!(backend.load(), Err(StorageError::NotFound)));
+
+        let mut profile = GrowthProfile::new();
+        profile.add_session(Session::new(1));
+        backend.save(&profile).unwrap();
+
+        let loaded = backend.load().unwrap();
+        assert_eq!(loaded.id, profile.id);
+        assert_eq!(loaded.sessions.len(), 1);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,118 @@
+use serde::{Deserialize, Serialize};
+
+use crate::export::STREAK_MILESTONES;
+use crate::session::Session;
+use crate::storage::{GrowthProfile, LifetimeStats};
+
+/// Notable changes caused by adding a session to a profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ProfileEvent {
+    StreakExtended { streak: u32 },
+    StreakBroken { previous_streak: u32 },
+    MilestoneReached { days: u32, tier: String },
+}
+
+/// Receiver for profile events
+pub trait EventSink {
+    fn emit(&self, event: &ProfileEvent);
+}
+
+impl<F: Fn(&ProfileEvent)> EventSink for F {
+    fn emit(&self, event: &ProfileEvent) {
+        self(event)
+    }
+}
+
+/// Events implied by lifetime stats changing from `before` to `after`
+pub fn streak_events(before: &LifetimeStats, after: &LifetimeStats) -> Vec<ProfileEvent> {
+    let (old, new) = (before.current_streak, after.current_streak);
+    let mut events = Vec::new();
+
+    if new > old {
+        events.push(ProfileEvent::StreakExtended { streak: new });
+        for (days, tier, _) in STREAK_MILESTONES.iter().rev() {
+            if old < *days && new >= *days {
+                events.push(ProfileEvent::MilestoneReached {
+                    days: *days,
+                    tier: tier.to_string(),
+                });
+            }
+        }
+    } else if new < old {
+        events.push(ProfileEvent::StreakBroken { previous_streak: old });
+    }
+
+    events
+}
+
+/// Add a session to the profile and emit any resulting events to `sink`
+pub fn add_session_notifying(
+    profile: &mut GrowthProfile,
+    session: Session,
+    sink: &dyn EventSink,
+) {
+    let before = profile.lifetime_stats.clone();
+    profile.add_session(session);
+
+    for event in streak_events(&before, &profile.lifetime_stats) {
+        sink.emit(&event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Duration, Utc};
+    use std::cell::RefCell;
+
+    #[test]
+    fn test_streak_events() {
+        let before = LifetimeStats {
+            current_streak: 6,
+            ..LifetimeStats::default()
+        };
+        let mut after = before.clone();
+        after.current_streak = 7;
+
+        assert_eq!(
+            streak_events(&before, &after),
+            vec![
+                ProfileEvent::StreakExtended { streak: 7 },
+                ProfileEvent::MilestoneReached { days: 7, tier: "bronze".to_string() },
+            ]
+        );
+        assert_eq!(
+            streak_events(&after, &before),
+            vec![ProfileEvent::StreakBroken { previous_streak: 7 }]
+        );
+        assert!(streak_events(&after, &after).is_empty());
+    }
+
+    #[test]
+    fn test_add_session_notifying() {
+        let mut profile = GrowthProfile::new();
+        for days_ago in 1..7 {
+            let mut session = Session::new(days_ago);
+            session.started_at = Utc::now() - Duration::days(days_ago as i64);
+            profile.add_session(session);
+        }
+        assert_eq!(profile.lifetime_stats.current_streak, 6);
+
+        let received = RefCell::new(Vec::new());
+        let sink = |event: &ProfileEvent| received.borrow_mut().push(event.clone());
+        add_session_notifying(&mut profile, Session::new(7), &sink);
+
+        let received = received.into_inner();
+        assert_eq!(received.len(), 2);
+        assert_eq!(
+            received[1],
+            ProfileEvent::MilestoneReached { days: 7, tier: "bronze".to_string() }
+        );
+
+        let received = RefCell::new(Vec::new());
+        let sink = |event: &ProfileEvent| received.borrow_mut().push(event.clone());
+        add_session_notifying(&mut profile, Session::new(8), &sink);
+        assert!(received.into_inner().is_empty());
+    }
+}
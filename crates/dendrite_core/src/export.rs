@@ -1,8 +1,26 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
+use serde_json::json;
 
-use crate::storage::GrowthProfile;
-use crate::visualization::{generate_heatmap, generate_language_breakdown};
+use crate::git::{commits_by_weekday, polyglot_commit_ratio};
+use crate::storage::{current_streak_dates, GrowthProfile};
+use crate::visualization::{
+    generate_heatmap, generate_hourly_distribution, generate_language_breakdown,
+    intensity_distribution, intensity_level, top_files,
+};
+
+/// Heatmap span used when an export doesn't specify one
+pub const DEFAULT_HEATMAP_WEEKS: u8 = 12;
+
+/// Minutes of active time per unit for each fun equivalent: a 25-minute
+/// pomodoro, a coffee every 2 hours, and an 8-hour workday
+const FUN_EQUIVALENT_MINUTES: [(&str, u64); 3] =
+    [("pomodoros", 25), ("coffees", 120), ("workdays", 480)];
+
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Monday", "Tuesday", "Wednesday", "Thursday", "Friday", "Saturday", "Sunday",
+];
 
 /// Export format options
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -13,6 +31,29 @@ pub enum ExportFormat {
     SvgHeatmap,
     BadgeSvg,
     BadgeUrl,
+    Ndjson,
+}
+
+/// How numbers are rendered in human-readable reports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberStyle {
+    #[default]
+    Plain,
+    Grouped,
+}
+
+/// How durations are rendered in exports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DurationFormat {
+    /// Raw milliseconds in JSON; hours and minutes in markdown
+    #[default]
+    Milliseconds,
+    /// Whole seconds, e.g. `"5025s"`
+    Seconds,
+    /// ISO 8601 durations, e.g. `"PT1H23M45S"`
+    Iso8601,
 }
 
 /// Configuration for portfolio export
@@ -22,6 +63,36 @@ pub struct ExportOptions {
     pub date_range: Option<(DateTime<Utc>, DateTime<Utc>)>,
     pub include_commits: bool,
     pub include_files: bool,
+    #[serde(default)]
+    pub number_style: NumberStyle,
+    /// List the N most active files in the markdown report
+    #[serde(default)]
+    pub top_files: Option<usize>,
+    /// Replace file paths with placeholders in the markdown report
+    #[serde(default)]
+    pub anonymize: bool,
+    /// Add a lighthearted "fun equivalents" section to the markdown report
+    #[serde(default)]
+    pub fun_equivalents: bool,
+    /// Regular expressions whose matches in commit messages are replaced with
+    /// `[redacted]` in JSON/NDJSON exports (markdown reports never include
+    /// commit messages). An invalid pattern fails the export rather than
+    /// leaking what it was meant to hide.
+    #[serde(default)]
+    pub commit_message_redactions: Vec<String>,
+    /// Report paused time as idle time: pause periods are folded into
+    /// `idle_periods` in JSON/NDJSON and into the markdown idle total
+    #[serde(default)]
+    pub count_paused_as_idle: bool,
+    /// With a non-default format, each elapsed-time `*_ms` field in the JSON
+    /// export gains a formatted string sibling (see `DURATION_FIELDS`), and
+    /// markdown durations use the format
+    #[serde(default)]
+    pub duration_format: DurationFormat,
+    /// Add a `derived` block to the JSON export with the badge SVG, badge URL,
+    /// language breakdown and summary, so one file can render a stats page
+    #[serde(default)]
+    pub embed_derived: bool,
 }
 
 impl Default for ExportOptions {
@@ -31,78 +102,731 @@ impl Default for ExportOptions {
             date_range: None,
             include_commits: true,
             include_files: true,
+            number_style: NumberStyle::Plain,
+            top_files: None,
+            anonymize: false,
+            fun_equivalents: false,
+            commit_message_redactions: Vec::new(),
+            count_paused_as_idle: false,
+            duration_format: DurationFormat::Milliseconds,
+            embed_derived: false,
         }
     }
 }
 
 /// Export profile as JSON
 pub fn export_json(profile: &GrowthProfile, options: &ExportOptions) -> Result<String, serde_json::Error> {
-    if options.date_range.is_some() || !options.include_commits || !options.include_files {
+    if options.date_range.is_some()
+        || !options.include_commits
+        || !options.include_files
+        || !options.commit_message_redactions.is_empty()
+        || options.count_paused_as_idle
+    {
         // Create a filtered copy
         let mut filtered = profile.clone();
-        
-        if let Some((start, end)) = options.date_range {
-            filtered.sessions.retain(|s| {
-                s.session.started_at >= start && s.session.started_at <= end
-            });
+        apply_filters(&mut filtered, options).map_err(serde::ser::Error::custom)?;
+        to_json_with_durations(&filtered, options)
+    } else {
+        to_json_with_durations(profile, options)
+    }
+}
+
+fn to_json_with_durations(
+    profile: &GrowthProfile,
+    options: &ExportOptions,
+) -> Result<String, serde_json::Error> {
+    let mut json = Vec::new();
+    write_json_with_durations(&mut json, profile, options)?;
+    String::from_utf8(json).map_err(serde::ser::Error::custom)
+}
+
+/// Pretty-print the profile as `export_json` does, applying the duration
+/// format and derived block, into any writer
+fn write_json_with_durations<W: std::io::Write>(
+    writer: W,
+    profile: &GrowthProfile,
+    options: &ExportOptions,
+) -> Result<(), serde_json::Error> {
+    let format = options.duration_format;
+    if format == DurationFormat::Milliseconds && !options.embed_derived {
+        return serde_json::to_writer_pretty(writer, profile);
+    }
+
+    let mut value = serde_json::to_value(profile)?;
+    if options.embed_derived {
+        if let Some(map) = value.as_object_mut() {
+            map.insert("derived".to_string(), derived_block(profile));
         }
+    }
+    if format != DurationFormat::Milliseconds {
+        add_duration_siblings(&mut value, format);
+    }
+    serde_json::to_writer_pretty(writer, &value)
+}
+
+/// Precomputed views embedded by `ExportOptions::embed_derived`
+fn derived_block(profile: &GrowthProfile) -> serde_json::Value {
+    json!({
+        "badge_svg": generate_badge_svg(profile),
+        "badge_url": generate_badge_url(profile),
+        "languages": generate_language_breakdown(profile),
+        "summary": profile_summary(profile),
+    })
+}
+
+/// Headline numbers for a profile overview
+pub fn profile_summary(profile: &GrowthProfile) -> serde_json::Value {
+    json!({
+        "tracking_since": profile.tracking_since(),
+        "days_tracked": profile.days_tracked(),
+        "total_sessions": profile.lifetime_stats.total_sessions,
+        "total_time_ms": profile.lifetime_stats.total_time_ms,
+        "current_streak": profile.lifetime_stats.current_streak,
+        "longest_streak": profile.lifetime_stats.longest_streak,
+    })
+}
+
+/// Millisecond fields that measure elapsed time, with the name of the
+/// formatted sibling added by a non-default `DurationFormat`. Settings such
+/// as `burst_window_ms` are left alone.
+const DURATION_FIELDS: [(&str, &str); 7] = [
+    ("total_time_ms", "total_time_duration"),
+    ("active_time_ms", "active_time_duration"),
+    ("engaged_time_ms", "engaged_time_duration"),
+    ("uncapped_time_ms", "uncapped_time_duration"),
+    ("daily_cap_ms", "daily_cap_duration"),
+    ("total_duration_ms", "total_duration"),
+    ("duration_ms", "duration"),
+];
+
+fn duration_sibling(key: &str) -> Option<&'static str> {
+    DURATION_FIELDS.iter().find(|(field, _)| *field == key).map(|(_, sibling)| *sibling)
+}
 
-        if !options.include_commits {
-            for stored_session in &mut filtered.sessions {
-                stored_session.session.commits.clear();
+/// Add a formatted duration string next to every `DURATION_FIELDS` integer
+fn add_duration_siblings(value: &mut serde_json::Value, format: DurationFormat) {
+    match value {
+        serde_json::Value::Object(map) => {
+            let siblings: Vec<(String, String)> = map
+                .iter()
+                .filter_map(|(key, v)| {
+                    let sibling = duration_sibling(key)?;
+                    let ms = v.as_u64()?;
+                    Some((sibling.to_string(), format_duration(ms, format)))
+                })
+                .collect();
+            for v in map.values_mut() {
+                add_duration_siblings(v, format);
+            }
+            for (key, formatted) in siblings {
+                map.insert(key, serde_json::Value::String(formatted));
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                add_duration_siblings(item, format);
             }
         }
+        _ => {}
+    }
+}
+
+/// Export sessions as newline-delimited JSON, one `StoredSession` per line.
+/// Fails when a redaction pattern is invalid.
+pub fn export_ndjson(profile: &GrowthProfile, options: &ExportOptions) -> Result<String, String> {
+    let mut filtered = profile.clone();
+    apply_filters(&mut filtered, options)?;
+
+    let mut ndjson = String::new();
+    for stored_session in &filtered.sessions {
+        if let Ok(line) = serde_json::to_string(stored_session) {
+            ndjson.push_str(&line);
+            ndjson.push('\n');
+        }
+    }
 
-        if !options.include_files {
-            for stored_session in &mut filtered.sessions {
-                stored_session.session.files_edited.clear();
+    Ok(ndjson)
+}
+
+/// Byte length of an export without building the output string. JSON and
+/// NDJSON are measured by streaming the same serialization into a counter.
+pub fn estimate_export_size(profile: &GrowthProfile, options: &ExportOptions) -> usize {
+    let mut counter = ByteCounter(0);
+
+    match options.format {
+        ExportFormat::Json => {
+            let mut filtered = profile.clone();
+            let _ = apply_filters(&mut filtered, options);
+            let _ = write_json_with_durations(&mut counter, &filtered, options);
+        }
+        ExportFormat::Ndjson => {
+            let mut filtered = profile.clone();
+            let _ = apply_filters(&mut filtered, options);
+            for stored_session in &filtered.sessions {
+                if serde_json::to_writer(&mut counter, stored_session).is_ok() {
+                    counter.0 += 1;
+                }
             }
         }
+        ExportFormat::Markdown => counter.0 = export_markdown(profile, options).len(),
+        ExportFormat::SvgHeatmap => {
+            counter.0 = export_heatmap_svg(profile, DEFAULT_HEATMAP_WEEKS).len()
+        }
+        ExportFormat::BadgeSvg => counter.0 = generate_badge_svg(profile).len(),
+        ExportFormat::BadgeUrl => counter.0 = generate_badge_url(profile).len(),
+    }
 
-        serde_json::to_string_pretty(&filtered)
-    } else {
-        serde_json::to_string_pretty(profile)
+    counter.0
+}
+
+/// `io::Write` sink that only counts bytes
+struct ByteCounter(usize);
+
+impl std::io::Write for ByteCounter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0 += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// JSON Schema (draft-07) describing the `GrowthProfile` export format
+pub fn export_json_schema() -> String {
+    let u64_map = json!({ "type": "object", "additionalProperties": { "type": "integer", "minimum": 0 } });
+    let date_time = json!({ "type": "string", "format": "date-time" });
+    let date = json!({ "type": "string", "format": "date" });
+    let uint = json!({ "type": "integer", "minimum": 0 });
+    let strings = json!({ "type": "array", "items": { "type": "string" } });
+
+    // Built separately to stay within the `json!` macro recursion limit
+    let session_properties = json!({
+        "id": uint,
+        "started_at": date_time,
+        "ended_at": { "type": ["string", "null"], "format": "date-time" },
+        "project": { "type": ["string", "null"] },
+        "counts_toward_stats": { "type": "boolean" },
+        "active_time_ms": uint,
+        "engaged_time_ms": uint,
+        "keystroke_count": uint,
+        "raw_keystroke_count": uint,
+        "burst_limit": uint,
+        "burst_window_ms": uint,
+        "track_keystroke_timing": { "type": "boolean" },
+        "keystroke_timestamps": { "type": "array", "items": date_time },
+        "files_edited": strings,
+        "normalize_paths": { "type": "boolean" },
+        "file_languages": { "type": "object", "additionalProperties": { "type": "string" } },
+        "file_times": u64_map,
+        "languages": u64_map,
+        "activity_times": { "$ref": "#/definitions/ActivityBreakdown" },
+        "idle_periods": { "type": "array", "items": { "$ref": "#/definitions/IdlePeriod" } },
+        "pause_periods": { "type": "array", "items": { "$ref": "#/definitions/IdlePeriod" } },
+        "commits": { "type": "array", "items": { "$ref": "#/definitions/CommitRef" } },
+        "pomodoro_intervals": uint,
+        "final_stats": {
+            "oneOf": [{ "$ref": "#/definitions/SessionStats" }, { "type": "null" }]
+        }
+    });
+
+    let schema = json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "title": "GrowthProfile",
+        "type": "object",
+        "required": ["id", "created_at", "sessions", "daily_aggregates", "lifetime_stats"],
+        "additionalProperties": false,
+        "properties": {
+            "schema_version": uint,
+            "id": { "type": "string" },
+            "created_at": date_time,
+            "sessions": { "type": "array", "items": { "$ref": "#/definitions/StoredSession" } },
+            "daily_aggregates": { "type": "array", "items": { "$ref": "#/definitions/DailyAggregate" } },
+            "lifetime_stats": { "$ref": "#/definitions/LifetimeStats" },
+            "freeze_tokens": uint,
+            "frozen_dates": { "type": "array", "items": date },
+            "freeze_tokens_granted_after": { "type": ["string", "null"], "format": "date" },
+            "utc_offset_minutes": { "type": "integer" },
+            "daily_cap_ms": { "type": ["integer", "null"], "minimum": 0 },
+            "derived": { "type": "object" }
+        },
+        "definitions": {
+            "StoredSession": {
+                "type": "object",
+                "required": ["session", "computed_stats"],
+                "additionalProperties": false,
+                "properties": {
+                    "session": { "$ref": "#/definitions/Session" },
+                    "computed_stats": { "$ref": "#/definitions/SessionStats" }
+                }
+            },
+            "Session": {
+                "type": "object",
+                "required": [
+                    "id", "started_at", "ended_at", "active_time_ms", "keystroke_count",
+                    "files_edited", "languages", "idle_periods", "commits"
+                ],
+                "additionalProperties": false,
+                "properties": session_properties
+            },
+            "SessionStats": {
+                "type": "object",
+                "required": ["total_duration_ms", "active_percentage", "primary_language", "commit_count"],
+                "additionalProperties": false,
+                "properties": {
+                    "total_duration_ms": uint,
+                    "active_percentage": { "type": "number" },
+                    "primary_language": { "type": ["string", "null"] },
+                    "commit_count": uint,
+                    "pomodoro_intervals": uint,
+                    "activity_breakdown": { "$ref": "#/definitions/ActivityBreakdown" },
+                    "interruptions_per_hour": { "type": "number" },
+                    "engaged_time_ms": uint
+                }
+            },
+            "ActivityBreakdown": {
+                "type": "object",
+                "additionalProperties": false,
+                "properties": {
+                    "edit": uint,
+                    "test_run": uint,
+                    "build": uint,
+                    "debug": uint
+                }
+            },
+            "IdlePeriod": {
+                "type": "object",
+                "required": ["started_at", "ended_at", "duration_ms"],
+                "additionalProperties": false,
+                "properties": {
+                    "started_at": date_time,
+                    "ended_at": { "type": ["string", "null"], "format": "date-time" },
+                    "duration_ms": uint
+                }
+            },
+            "CommitRef": {
+                "type": "object",
+                "required": ["hash", "short_hash", "message", "timestamp", "files_changed"],
+                "additionalProperties": false,
+                "properties": {
+                    "hash": { "type": "string" },
+                    "short_hash": { "type": "string" },
+                    "message": { "type": "string" },
+                    "timestamp": date_time,
+                    "files_changed": strings
+                }
+            },
+            "DailyAggregate": {
+                "type": "object",
+                "required": [
+                    "date", "total_time_ms", "total_keystrokes", "files_count",
+                    "sessions_count", "commits_count", "languages"
+                ],
+                "additionalProperties": false,
+                "properties": {
+                    "date": date,
+                    "total_time_ms": uint,
+                    "total_keystrokes": uint,
+                    "files_count": uint,
+                    "sessions_count": uint,
+                    "commits_count": uint,
+                    "languages": u64_map,
+                    "uncapped_time_ms": uint
+                }
+            },
+            "LifetimeStats": {
+                "type": "object",
+                "required": [
+                    "total_time_ms", "total_keystrokes", "total_sessions", "total_commits",
+                    "current_streak", "longest_streak", "languages"
+                ],
+                "additionalProperties": false,
+                "properties": {
+                    "total_time_ms": uint,
+                    "total_keystrokes": uint,
+                    "total_sessions": uint,
+                    "total_commits": uint,
+                    "current_streak": uint,
+                    "longest_streak": uint,
+                    "languages": u64_map,
+                    "total_pomodoros": uint
+                }
+            }
+        }
+    });
+
+    let mut schema = schema;
+    allow_duration_siblings(&mut schema);
+    serde_json::to_string_pretty(&schema).unwrap_or_default()
+}
+
+/// Declare the optional string sibling of every duration field in the schema
+fn allow_duration_siblings(schema: &mut serde_json::Value) {
+    if let Some(properties) = schema.get_mut("properties").and_then(|p| p.as_object_mut()) {
+        let siblings: Vec<&str> = properties.keys().filter_map(|k| duration_sibling(k)).collect();
+        for sibling in siblings {
+            properties.insert(sibling.to_string(), json!({ "type": "string" }));
+        }
+    }
+    if let Some(definitions) = schema.get_mut("definitions").and_then(|d| d.as_object_mut()) {
+        for definition in definitions.values_mut() {
+            allow_duration_siblings(definition);
+        }
+    }
+}
+
+/// Apply the date range and content filters from `options` to a profile copy
+fn apply_filters(filtered: &mut GrowthProfile, options: &ExportOptions) -> Result<(), String> {
+    if let Some((start, end)) = options.date_range {
+        filtered.sessions.retain(|s| {
+            s.session.started_at >= start && s.session.started_at <= end
+        });
+    }
+
+    if !options.include_commits {
+        for stored_session in &mut filtered.sessions {
+            stored_session.session.commits.clear();
+        }
+    }
+
+    if options.count_paused_as_idle {
+        for stored_session in &mut filtered.sessions {
+            let session = &mut stored_session.session;
+            session.idle_periods.append(&mut session.pause_periods);
+            session.idle_periods.sort_by_key(|p| p.started_at);
+        }
+    }
+
+    if !options.commit_message_redactions.is_empty() {
+        let pattern = compile_redactions(&options.commit_message_redactions)?;
+        for stored_session in &mut filtered.sessions {
+            for commit in &mut stored_session.session.commits {
+                commit.message = redact(&commit.message, pattern.as_ref());
+            }
+        }
+    }
+
+    if !options.include_files {
+        for stored_session in &mut filtered.sessions {
+            stored_session.session.files_edited.clear();
+            stored_session.session.file_languages.clear();
+            stored_session.session.file_times.clear();
+        }
+    }
+
+    Ok(())
+}
+
+/// Compile redaction patterns into a single alternation, skipping empty ones.
+/// Each pattern is checked on its own so errors name the offending pattern.
+pub fn compile_redactions(patterns: &[String]) -> Result<Option<regex::Regex>, String> {
+    let mut parts = Vec::new();
+    for pattern in patterns.iter().filter(|p| !p.is_empty()) {
+        regex::Regex::new(pattern)
+            .map_err(|e| format!("invalid redaction pattern `{}`: {}", pattern, e))?;
+        parts.push(format!("(?:{})", pattern));
     }
+    if parts.is_empty() {
+        return Ok(None);
+    }
+
+    regex::Regex::new(&parts.join("|")).map(Some).map_err(|e| e.to_string())
+}
+
+/// Replace every non-empty match of `pattern` in `text` with `[redacted]`
+fn redact(text: &str, pattern: Option<&regex::Regex>) -> String {
+    let Some(pattern) = pattern else {
+        return text.to_string();
+    };
+
+    pattern
+        .replace_all(text, |caps: &regex::Captures| {
+            if caps[0].is_empty() { "" } else { "[redacted]" }
+        })
+        .into_owned()
 }
 
 /// Export profile as Markdown report
-pub fn export_markdown(profile: &GrowthProfile, _options: &ExportOptions) -> String {
+pub fn export_markdown(profile: &GrowthProfile, options: &ExportOptions) -> String {
+    let num = |n: u64| format_number(n, options.number_style);
     let mut md = String::new();
     
     md.push_str("# Learning Growth Report\n\n");
     md.push_str(&format!("**Profile ID:** `{}`\n", profile.id));
-    md.push_str(&format!("**Created:** {}\n\n", profile.created_at.format("%Y-%m-%d %H:%M:%S UTC")));
+    md.push_str(&format!("**Created:** {}\n", profile.created_at.format("%Y-%m-%d %H:%M:%S UTC")));
+    md.push_str(&format!(
+        "**Tracking Since:** {} ({} days)\n\n",
+        profile.tracking_since().format("%Y-%m-%d"),
+        num(profile.days_tracked().max(0) as u64)
+    ));
     
     md.push_str("## Lifetime Statistics\n\n");
-    let hours = profile.lifetime_stats.total_time_ms / 1000 / 3600;
-    let minutes = (profile.lifetime_stats.total_time_ms / 1000 / 60) % 60;
-    md.push_str(&format!("- **Total Active Time:** {}h {}m\n", hours, minutes));
-    md.push_str(&format!("- **Total Sessions:** {}\n", profile.lifetime_stats.total_sessions));
-    md.push_str(&format!("- **Total Keystrokes:** {}\n", profile.lifetime_stats.total_keystrokes));
-    md.push_str(&format!("- **Total Commits:** {}\n", profile.lifetime_stats.total_commits));
+    let duration = |ms: u64| format_duration(ms, options.duration_format);
+    md.push_str(&format!(
+        "- **Total Active Time:** {}\n",
+        duration(profile.lifetime_stats.total_time_ms)
+    ));
+    let idle_ms: u64 = profile
+        .counted_sessions()
+        .map(|s| {
+            let paused_ms = if options.count_paused_as_idle {
+                s.session.paused_time_ms()
+            } else {
+                0
+            };
+            s.session.idle_time_ms() + paused_ms
+        })
+        .sum();
+    md.push_str(&format!("- **Total Idle Time:** {}\n", duration(idle_ms)));
+    md.push_str(&format!("- **Total Sessions:** {}\n", num(profile.lifetime_stats.total_sessions as u64)));
+    md.push_str(&format!("- **Total Keystrokes:** {}\n", num(profile.lifetime_stats.total_keystrokes)));
+    md.push_str(&format!("- **Total Commits:** {}\n", num(profile.lifetime_stats.total_commits as u64)));
     md.push_str(&format!("- **Current Streak:** {} days\n", profile.lifetime_stats.current_streak));
-    md.push_str(&format!("- **Longest Streak:** {} days\n\n", profile.lifetime_stats.longest_streak));
+    md.push_str(&format!("- **Longest Streak:** {} days\n", profile.lifetime_stats.longest_streak));
+    let levels = intensity_distribution(profile, DEFAULT_HEATMAP_WEEKS);
+    md.push_str(&format!(
+        "- **Activity ({} weeks):** {} days max, {} high, {} moderate, {} light, {} inactive\n\n",
+        DEFAULT_HEATMAP_WEEKS, levels[4], levels[3], levels[2], levels[1], levels[0]
+    ));
     
+    if options.fun_equivalents {
+        md.push_str("## Fun Equivalents\n\n");
+        let equivalents = fun_equivalents(profile);
+        for (name, _) in FUN_EQUIVALENT_MINUTES {
+            md.push_str(&format!("- **{}:** {}\n", name, num(equivalents[name])));
+        }
+        md.push('\n');
+    }
+
     md.push_str("## Language Breakdown\n\n");
     let languages = generate_language_breakdown(profile);
     for lang in &languages {
         let hours = lang.time_ms / 1000 / 3600;
-        md.push_str(&format!("- **{}**: {}h ({:.1}%)\n", lang.language, hours, lang.percentage));
+        md.push_str(&format!(
+            "- **{}**: {}h ({:.1}% of time, {:.1}% of files)\n",
+            lang.language, hours, lang.percentage, lang.files_percentage
+        ));
+    }
+
+    if let (Some(n), true) = (options.top_files, options.include_files) {
+        let files = top_files(profile, n);
+        if !files.is_empty() {
+            let total: u64 = profile
+                .counted_sessions()
+                .flat_map(|s| s.session.file_times.values())
+                .sum();
+
+            md.push_str("\n## Most Active Files\n\n");
+            for (i, (path, time_ms)) in files.iter().enumerate() {
+                let name = if options.anonymize {
+                    anonymize_path(path, i + 1)
+                } else {
+                    path.clone()
+                };
+                let share = *time_ms as f32 / total as f32 * 100.0;
+                md.push_str(&format!(
+                    "{}. `{}`: {} ({:.1}%)\n",
+                    i + 1,
+                    name,
+                    duration(*time_ms),
+                    share
+                ));
+            }
+        }
     }
     
+    if options.include_commits && profile.lifetime_stats.total_commits > 0 {
+        md.push_str("\n## Commits\n\n");
+        md.push_str(&format!("- **Total Commits:** {}\n", num(profile.lifetime_stats.total_commits as u64)));
+
+        md.push_str(&format!(
+            "- **Polyglot Commits:** {:.1}%\n",
+            polyglot_commit_ratio(profile) * 100.0
+        ));
+
+        let weekdays = commits_by_weekday(profile);
+        let busiest = (0..7).rev().max_by_key(|&i| weekdays[i]).unwrap_or(0);
+        if weekdays[busiest] > 0 {
+            md.push_str(&format!(
+                "- **Busiest Commit Day:** {} ({} commits)\n",
+                WEEKDAY_NAMES[busiest],
+                num(weekdays[busiest] as u64)
+            ));
+        }
+    }
+
     md.push_str("\n## Recent Activity\n\n");
-    md.push_str(&format!("Total sessions recorded: {}\n", profile.sessions.len()));
+    let recorded = profile.counted_sessions().count() as u64;
+    md.push_str(&format!("Total sessions recorded: {}\n", num(recorded)));
     
-    if let Some(last_session) = profile.sessions.last() {
+    if let Some(last_session) = profile.counted_sessions().last() {
         md.push_str(&format!("\nLast session: {}\n", last_session.session.started_at.format("%Y-%m-%d %H:%M:%S UTC")));
     }
     
     md
 }
 
+/// Lighthearted equivalents of total active time, keyed by name
+/// (see `FUN_EQUIVALENT_MINUTES` for the divisors)
+pub fn fun_equivalents(profile: &GrowthProfile) -> HashMap<String, u64> {
+    let minutes = profile.lifetime_stats.total_time_ms / 1000 / 60;
+    FUN_EQUIVALENT_MINUTES
+        .iter()
+        .map(|(name, divisor)| (name.to_string(), minutes / divisor))
+        .collect()
+}
+
+/// Generate a short plain-text digest of the last 7 days
+pub fn weekly_digest(profile: &GrowthProfile) -> String {
+    let today = profile.today();
+    let week_start = today - Duration::days(6);
+    let prior_start = week_start - Duration::days(7);
+
+    let mut this_week_ms = 0u64;
+    let mut prior_week_ms = 0u64;
+    let mut days_active = 0u32;
+    let mut commits = 0u32;
+    let mut languages: HashMap<&str, u64> = HashMap::new();
+
+    for daily in &profile.daily_aggregates {
+        if daily.date >= week_start && daily.date <= today {
+            this_week_ms += daily.total_time_ms;
+            commits += daily.commits_count;
+            if daily.total_time_ms > 0 || daily.sessions_count > 0 {
+                days_active += 1;
+            }
+            for (lang, time) in &daily.languages {
+                *languages.entry(lang.as_str()).or_insert(0) += time;
+            }
+        } else if daily.date >= prior_start && daily.date < week_start {
+            prior_week_ms += daily.total_time_ms;
+        }
+    }
+
+    let mut digest = format!(
+        "Dendrite weekly digest ({} to {})\n\n",
+        week_start.format("%Y-%m-%d"),
+        today.format("%Y-%m-%d")
+    );
+
+    let delta = if this_week_ms >= prior_week_ms {
+        format!("+{}", format_duration_hm(this_week_ms - prior_week_ms))
+    } else {
+        format!("-{}", format_duration_hm(prior_week_ms - this_week_ms))
+    };
+
+    if days_active == 0 {
+        digest.push_str("No coding activity this week.\n");
+        digest.push_str(&format!("Total time: 0h 0m ({} vs last week)\n", delta));
+    } else {
+        digest.push_str(&format!(
+            "Total time: {} ({} vs last week)\n",
+            format_duration_hm(this_week_ms),
+            delta
+        ));
+        digest.push_str(&format!("Days active: {}/7\n", days_active));
+
+        if let Some((lang, _)) = languages.iter().max_by_key(|(_, time)| **time) {
+            digest.push_str(&format!("Top language: {}\n", lang));
+        }
+
+        digest.push_str(&format!("Commits: {}\n", commits));
+    }
+
+    let stats = &profile.lifetime_stats;
+    if stats.current_streak > 0 {
+        digest.push_str(&format!(
+            "Streak: {} days (longest {})\n",
+            stats.current_streak, stats.longest_streak
+        ));
+    } else {
+        digest.push_str("Streak: none active - code today to start one\n");
+    }
+
+    digest
+}
+
+/// Format a number for display, adding thousands separators when grouped
+pub fn format_number(n: u64, style: NumberStyle) -> String {
+    let digits = n.to_string();
+    if style == NumberStyle::Plain {
+        return digits;
+    }
+
+    let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+/// Placeholder for a file path that keeps only its extension
+fn anonymize_path(path: &str, index: usize) -> String {
+    let file_name = path.rsplit('/').next().unwrap_or(path);
+    match file_name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("file-{}.{}", index, ext),
+        _ => format!("file-{}", index),
+    }
+}
+
+/// ISO 8601 duration in hours, minutes and seconds with zero components
+/// omitted, e.g. `PT1H23M` or `PT45S`; zero is `PT0S`
+pub fn iso8601_duration(ms: u64) -> String {
+    let seconds = ms / 1000;
+    let (hours, minutes, seconds) = (seconds / 3600, (seconds / 60) % 60, seconds % 60);
+
+    let mut duration = "PT".to_string();
+    if hours > 0 {
+        duration.push_str(&format!("{}H", hours));
+    }
+    if minutes > 0 {
+        duration.push_str(&format!("{}M", minutes));
+    }
+    if seconds > 0 || (hours == 0 && minutes == 0) {
+        duration.push_str(&format!("{}S", seconds));
+    }
+    duration
+}
+
+fn format_duration(ms: u64, format: DurationFormat) -> String {
+    match format {
+        DurationFormat::Milliseconds => format_duration_hm(ms),
+        DurationFormat::Seconds => format!("{}s", ms / 1000),
+        DurationFormat::Iso8601 => iso8601_duration(ms),
+    }
+}
+
+fn format_duration_hm(ms: u64) -> String {
+    let hours = ms / 1000 / 3600;
+    let minutes = (ms / 1000 / 60) % 60;
+    format!("{}h {}m", hours, minutes)
+}
+
 /// Generate SVG heatmap
+///
+/// `weeks` is clamped to `1..=MAX_HEATMAP_WEEKS`, so the output is always a
+/// well-formed SVG with at least one week of cells.
 pub fn export_heatmap_svg(profile: &GrowthProfile, weeks: u8) -> String {
+    export_heatmap_svg_with_streak(profile, weeks, false)
+}
+
+/// Generate SVG heatmap, optionally outlining the days of the current streak
+pub fn export_heatmap_svg_with_streak(
+    profile: &GrowthProfile,
+    weeks: u8,
+    highlight_streak: bool,
+) -> String {
     let heatmap = generate_heatmap(profile, weeks);
+    let streak_dates = if highlight_streak {
+        current_streak_dates(profile)
+    } else {
+        Vec::new()
+    };
+    let weeks = heatmap.weeks;
     let cell_size = 12;
     let cell_gap = 2;
     let width = weeks as usize * (cell_size + cell_gap) + 40;
@@ -120,10 +844,324 @@ pub fn export_heatmap_svg(profile: &GrowthProfile, weeks: u8) -> String {
         let x = 20 + (weeks - cell.week - 1) as usize * (cell_size + cell_gap);
         let y = 20 + cell.day as usize * (cell_size + cell_gap);
         let color = intensity_to_color(cell.intensity);
+        let stroke = if streak_dates.contains(&cell.date) {
+            " stroke=\"#f0883e\" stroke-width=\"1\""
+        } else {
+            ""
+        };
+
+        svg.push_str(&format!(
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" rx=\"2\"{}/>",
+            x, y, cell_size, cell_size, color, stroke
+        ));
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// GitHub-style contributions card: an active-day count header, the heatmap,
+/// a current/longest streak footer, and a Less-to-More color legend
+pub fn generate_contributions_card_svg(profile: &GrowthProfile, weeks: u8) -> String {
+    let heatmap = generate_heatmap(profile, weeks);
+    let weeks = heatmap.weeks;
+    let active_days = heatmap.cells.iter().filter(|c| c.raw_minutes > 0).count();
+    let heatmap_svg = export_heatmap_svg(profile, weeks);
+
+    let heatmap_width = weeks as usize * 14 + 40;
+    let heatmap_height = 7 * 14 + 40;
+    let width = heatmap_width.max(360);
+    let header_height = 24;
+    let footer_y = header_height + heatmap_height + 8;
+    let height = footer_y + 16;
+
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\" \
+         font-family=\"-apple-system,Segoe UI,Helvetica,Arial,sans-serif\" font-size=\"12\">",
+        width, height
+    );
+    svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>");
+    svg.push_str(&format!(
+        "<text x=\"20\" y=\"18\" fill=\"#24292f\">{} active {} in the last {} weeks</text>",
+        active_days,
+        if active_days == 1 { "day" } else { "days" },
+        weeks
+    ));
+
+    // Nest the heatmap as its own viewport below the header
+    let positioned = format!("<svg x=\"0\" y=\"{}\" ", header_height);
+    svg.push_str(&heatmap_svg.replacen("<svg ", &positioned, 1));
+
+    svg.push_str(&format!(
+        "<text x=\"20\" y=\"{}\" fill=\"#57606a\">\
+         Current streak: {} days \u{b7} Longest streak: {} days</text>",
+        footer_y,
+        profile.lifetime_stats.current_streak,
+        profile.lifetime_stats.longest_streak
+    ));
+
+    // Legend: Less [5 swatches] More, right-aligned
+    let legend_x = width - 20 - 30 - 5 * 14 - 34;
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" fill=\"#57606a\">Less</text>",
+        legend_x, footer_y
+    ));
+    for (i, intensity) in [0.0, 0.2, 0.4, 0.6, 1.0].into_iter().enumerate() {
+        svg.push_str(&format!(
+            "<rect class=\"legend\" x=\"{}\" y=\"{}\" width=\"10\" height=\"10\" \
+             fill=\"{}\" rx=\"2\"/>",
+            legend_x + 30 + i * 14,
+            footer_y - 9,
+            intensity_to_color(intensity)
+        ));
+    }
+    svg.push_str(&format!(
+        "<text x=\"{}\" y=\"{}\" fill=\"#57606a\">More</text>",
+        legend_x + 30 + 5 * 14 + 2,
+        footer_y
+    ));
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Block characters for heatmap intensity levels 0-4
+const ASCII_HEATMAP_LEVELS: [char; 5] = [' ', '░', '▒', '▓', '█'];
+
+/// Render the daily heatmap as 7 lines (Monday-Sunday) of `weeks` block
+/// characters, oldest week first, for terminal output
+pub fn render_ascii_heatmap(profile: &GrowthProfile, weeks: u8) -> String {
+    let heatmap = generate_heatmap(profile, weeks);
+    let weeks = heatmap.weeks as usize;
+    let mut grid = vec![vec![ASCII_HEATMAP_LEVELS[0]; weeks]; 7];
+
+    for cell in &heatmap.cells {
+        let column = weeks - cell.week as usize - 1;
+        grid[cell.day as usize][column] = ASCII_HEATMAP_LEVELS[intensity_level(cell.intensity)];
+    }
+
+    grid.iter()
+        .map(|row| row.iter().collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Export the daily heatmap cells as CSV (`date,minutes,level`), oldest first
+pub fn export_heatmap_csv(profile: &GrowthProfile, weeks: u8) -> String {
+    let mut cells = generate_heatmap(profile, weeks).cells;
+    cells.sort_by_key(|c| c.date);
+
+    let mut csv = String::from("date,minutes,level\n");
+    for cell in &cells {
+        csv.push_str(&format!(
+            "{},{},{}\n",
+            cell.date.format("%Y-%m-%d"),
+            cell.raw_minutes,
+            intensity_level(cell.intensity)
+        ));
+    }
+    csv
+}
+
+/// File names written by `export_bundle`, in archive order
+pub const BUNDLE_ENTRIES: [&str; 4] = ["profile.json", "report.md", "heatmap.svg", "heatmap.csv"];
+
+/// Bundle the JSON profile, markdown report, heatmap SVG and heatmap CSV
+/// into a single ZIP archive. Entries are stored uncompressed to keep the
+/// WASM build free of compression backends. Fails when the JSON export does
+/// (e.g. an invalid redaction pattern) or the archive can't be written.
+pub fn export_bundle(profile: &GrowthProfile, options: &ExportOptions) -> Result<Vec<u8>, String> {
+    let contents = [
+        export_json(profile, options).map_err(|e| e.to_string())?,
+        export_markdown(profile, options),
+        export_heatmap_svg(profile, DEFAULT_HEATMAP_WEEKS),
+        export_heatmap_csv(profile, DEFAULT_HEATMAP_WEEKS),
+    ];
+    write_zip(BUNDLE_ENTRIES.iter().copied().zip(contents.iter())).map_err(|e| e.to_string())
+}
+
+fn write_zip<'a>(
+    entries: impl Iterator<Item = (&'a str, &'a String)>,
+) -> zip::result::ZipResult<Vec<u8>> {
+    use std::io::Write;
+    use zip::write::SimpleFileOptions;
+
+    let mut writer = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let file_options =
+        SimpleFileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    for (name, content) in entries {
+        writer.start_file(name, file_options)?;
+        writer.write_all(content.as_bytes())?;
+    }
+    Ok(writer.finish()?.into_inner())
+}
+
+/// Generate a compact sparkline SVG of active minutes over the last N days
+pub fn generate_sparkline_svg(profile: &GrowthProfile, days: u32) -> String {
+    let days = days.max(1);
+    let today = profile.today();
+    let step = 4;
+    let height = 20;
+    let padding = 2;
+    let width = (days - 1) as usize * step + padding * 2;
+
+    let minutes: Vec<u64> = (0..days)
+        .rev()
+        .map(|offset| {
+            let date = today - Duration::days(offset as i64);
+            profile
+                .daily_aggregate(date)
+                .map_or(0, |d| d.total_time_ms / 1000 / 60)
+        })
+        .collect();
+    let max_minutes = minutes.iter().copied().max().unwrap_or(0);
+
+    let baseline = (height - padding) as f32;
+    let points: Vec<String> = minutes
+        .iter()
+        .enumerate()
+        .map(|(i, m)| {
+            let x = padding + i * step;
+            let y = if max_minutes > 0 {
+                baseline - (*m as f32 / max_minutes as f32) * (height - padding * 2) as f32
+            } else {
+                baseline
+            };
+            format!("{},{:.1}", x, y)
+        })
+        .collect();
+
+    format!(
+        "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">\
+         <polyline points=\"{}\" fill=\"none\" stroke=\"#40c463\" stroke-width=\"1.5\"/>\
+         </svg>",
+        width,
+        height,
+        points.join(" ")
+    )
+}
+
+/// Generate a 24-bar chart SVG of active time per hour of day, scaled to the
+/// busiest hour, in the profile's local time. With no activity every bar sits
+/// flat on the baseline.
+pub fn generate_hourly_barchart_svg(profile: &GrowthProfile) -> String {
+    let hourly = generate_hourly_distribution(profile, profile.utc_offset_minutes);
+    let max_time = hourly.values().copied().max().unwrap_or(0);
+
+    let bar_width = 10;
+    let bar_gap = 2;
+    let chart_height = 100;
+    let left = 10;
+    let baseline = 10 + chart_height;
+    let width = 24 * (bar_width + bar_gap) + left * 2;
+    let height = baseline + 20;
+
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        width, height
+    );
+    svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>");
+    svg.push_str(&format!(
+        "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"#d0d7de\"/>",
+        left,
+        baseline,
+        width - left,
+        baseline
+    ));
+
+    for hour in 0..24u8 {
+        let time = hourly.get(&hour).copied().unwrap_or(0);
+        let bar_height = if max_time > 0 {
+            (time as f64 / max_time as f64 * chart_height as f64).round() as usize
+        } else {
+            0
+        };
+        let x = left + hour as usize * (bar_width + bar_gap);
+
+        svg.push_str(&format!(
+            "<rect class=\"bar\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#40c463\"/>",
+            x,
+            baseline - bar_height,
+            bar_width,
+            bar_height
+        ));
+
+        if hour % 6 == 0 {
+            svg.push_str(&format!(
+                "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"8\">{}</text>",
+                x,
+                baseline + 12,
+                hour
+            ));
+        }
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+/// Generate a single-month calendar SVG (Monday-first, 7 columns) with
+/// active days shaded by intensity. Returns an empty string for an invalid month.
+pub fn generate_month_calendar_svg(profile: &GrowthProfile, year: i32, month: u32) -> String {
+    let first = match NaiveDate::from_ymd_opt(year, month, 1) {
+        Some(d) => d,
+        None => return String::new(),
+    };
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)
+    };
+    let days_in_month = match next_month {
+        Some(next) => (next - first).num_days() as u32,
+        None => return String::new(),
+    };
+
+    let minutes: Vec<u64> = (0..days_in_month)
+        .map(|offset| {
+            profile
+                .daily_aggregate(first + Duration::days(offset as i64))
+                .map_or(0, |d| d.total_time_ms / 1000 / 60)
+        })
+        .collect();
+    let max_minutes = minutes.iter().copied().max().unwrap_or(0);
+
+    let cell_size = 20;
+    let cell_gap = 2;
+    let leading_blanks = first.weekday().num_days_from_monday() as usize;
+    let rows = (leading_blanks + days_in_month as usize).div_ceil(7);
+    let width = 7 * (cell_size + cell_gap) + 20;
+    let height = rows * (cell_size + cell_gap) + 40;
+
+    let mut svg = format!(
+        "<svg width=\"{}\" height=\"{}\" xmlns=\"http://www.w3.org/2000/svg\">",
+        width, height
+    );
+    svg.push_str("<rect width=\"100%\" height=\"100%\" fill=\"#ffffff\"/>");
+    svg.push_str(&format!(
+        "<text x=\"10\" y=\"20\" font-family=\"sans-serif\" font-size=\"12\">{}</text>",
+        first.format("%B %Y")
+    ));
+
+    for (i, m) in minutes.iter().enumerate() {
+        let slot = leading_blanks + i;
+        let x = 10 + (slot % 7) * (cell_size + cell_gap);
+        let y = 30 + (slot / 7) * (cell_size + cell_gap);
+        let intensity = if max_minutes > 0 {
+            *m as f32 / max_minutes as f32
+        } else {
+            0.0
+        };
 
         svg.push_str(&format!(
             "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\" rx=\"2\"/>",
-            x, y, cell_size, cell_size, color
+            x, y, cell_size, cell_size, intensity_to_color(intensity)
+        ));
+        svg.push_str(&format!(
+            "<text x=\"{}\" y=\"{}\" font-family=\"sans-serif\" font-size=\"8\">{}</text>",
+            x + 2,
+            y + 9,
+            i + 1
         ));
     }
 
@@ -134,10 +1172,30 @@ pub fn export_heatmap_svg(profile: &GrowthProfile, weeks: u8) -> String {
 /// Generate a badge SVG showing streak
 pub fn generate_badge_svg(profile: &GrowthProfile) -> String {
     let streak = profile.lifetime_stats.current_streak;
-    let label = "streak";
-    let value = format!("{} days", streak);
+    render_badge_svg("streak", &format!("{} days", streak), "#4c1")
+}
+
+/// Streak milestone tiers as (minimum days, label, color), highest first
+pub(crate) const STREAK_MILESTONES: [(u32, &str, &str); 4] = [
+    (365, "diamond", "#b9f2ff"),
+    (100, "gold", "#dfb317"),
+    (30, "silver", "#9f9f9f"),
+    (7, "bronze", "#cd7f32"),
+];
+
+/// Streak badge labelled and colored by the highest milestone reached
+/// (7/30/100/365 days); streaks below 7 days use the plain streak badge colors
+pub fn generate_milestone_badge_svg(profile: &GrowthProfile) -> String {
+    let streak = profile.lifetime_stats.current_streak;
+    let (label, color) = STREAK_MILESTONES
+        .iter()
+        .find(|(days, _, _)| streak >= *days)
+        .map_or(("streak", "#4c1"), |(_, label, color)| (*label, *color));
+    render_badge_svg(label, &format!("{} days", streak), color)
+}
 
-    // Simple shields.io-style badge
+/// Simple shields.io-style badge
+fn render_badge_svg(label: &str, value: &str, color: &str) -> String {
     format!(
         "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"120\" height=\"20\">\n\
           <linearGradient id=\"b\" x2=\"0\" y2=\"100%\">\n\
@@ -145,8 +1203,8 @@ pub fn generate_badge_svg(profile: &GrowthProfile) -> String {
             <stop offset=\"1\" stop-opacity=\".1\"/>\n\
           </linearGradient>\n\
           <rect rx=\"3\" width=\"120\" height=\"20\" fill=\"#555\"/>\n\
-          <rect rx=\"3\" x=\"50\" width=\"70\" height=\"20\" fill=\"#4c1\"/>\n\
-          <path fill=\"#4c1\" d=\"M50 0h4v20h-4z\"/>\n\
+          <rect rx=\"3\" x=\"50\" width=\"70\" height=\"20\" fill=\"{color}\"/>\n\
+          <path fill=\"{color}\" d=\"M50 0h4v20h-4z\"/>\n\
           <rect rx=\"3\" width=\"120\" height=\"20\" fill=\"url(#b)\"/>\n\
           <g fill=\"#fff\" text-anchor=\"middle\" font-family=\"DejaVu Sans,Verdana,Geneva,sans-serif\" font-size=\"11\">\n\
             <text x=\"25\" y=\"15\" fill=\"#010101\" fill-opacity=\".3\">{}</text>\n\
@@ -155,7 +1213,11 @@ pub fn generate_badge_svg(profile: &GrowthProfile) -> String {
             <text x=\"85\" y=\"14\">{}</text>\n\
           </g>\n\
         </svg>",
-        label, label, value, value
+        label,
+        label,
+        value,
+        value,
+        color = color
     )
 }
 
@@ -169,17 +1231,14 @@ pub fn generate_badge_url(profile: &GrowthProfile) -> String {
 }
 
 fn intensity_to_color(intensity: f32) -> String {
-    if intensity == 0.0 {
-        "#ebedf0".to_string()
-    } else if intensity < 0.25 {
-        "#9be9a8".to_string()
-    } else if intensity < 0.5 {
-        "#40c463".to_string()
-    } else if intensity < 0.75 {
-        "#30a14e".to_string()
-    } else {
-        "#216e39".to_string()
+    match intensity_level(intensity) {
+        0 => "#ebedf0",
+        1 => "#9be9a8",
+        2 => "#40c463",
+        3 => "#30a14e",
+        _ => "#216e39",
     }
+    .to_string()
 }
 
 #[cfg(test)]
@@ -194,6 +1253,230 @@ mod tests {
         assert!(json.contains(&profile.id));
     }
 
+    #[test]
+    fn test_export_bundle() {
+        use std::io::Read;
+
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        profile.add_session(session);
+
+        let bytes = export_bundle(&profile, &ExportOptions::default()).unwrap();
+        let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes)).unwrap();
+        assert_eq!(archive.len(), BUNDLE_ENTRIES.len());
+        for name in BUNDLE_ENTRIES {
+            let mut content = String::new();
+            archive.by_name(name).unwrap().read_to_string(&mut content).unwrap();
+            assert!(!content.is_empty(), "{} is empty", name);
+        }
+
+        let options = ExportOptions {
+            commit_message_redactions: vec!["[A-Z".to_string()],
+            ..ExportOptions::default()
+        };
+        let err = export_bundle(&profile, &options).unwrap_err();
+        assert!(err.contains("invalid redaction pattern `[A-Z`"));
+    }
+
+    #[test]
+    fn test_export_ndjson() {
+        let mut profile = GrowthProfile::new();
+        let mut old_session = crate::session::Session::new(1);
+        old_session.started_at = Utc::now() - Duration::days(30);
+        profile.add_session(old_session);
+        let mut session = crate::session::Session::new(2);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        profile.add_session(session);
+        profile.add_session(crate::session::Session::new(3));
+
+        let options = ExportOptions {
+            date_range: Some((Utc::now() - Duration::days(1), Utc::now())),
+            include_files: false,
+            ..ExportOptions::default()
+        };
+        let ndjson = export_ndjson(&profile, &options).unwrap();
+
+        let lines: Vec<&str> = ndjson.lines().collect();
+        assert_eq!(lines.len(), 2);
+        for line in lines {
+            let stored: crate::storage::StoredSession = serde_json::from_str(line).unwrap();
+            assert!(stored.session.files_edited.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_estimate_export_size() {
+        let mut profile = GrowthProfile::new();
+        for i in 0..20 {
+            let mut session = crate::session::Session::new(i);
+            session.record_file_edit(format!("src/file_{}.rs", i), "rust".to_string());
+            profile.add_session(session);
+        }
+
+        let variants = [
+            ExportOptions::default(),
+            ExportOptions {
+                duration_format: DurationFormat::Iso8601,
+                ..ExportOptions::default()
+            },
+            ExportOptions {
+                duration_format: DurationFormat::Seconds,
+                ..ExportOptions::default()
+            },
+            ExportOptions {
+                embed_derived: true,
+                ..ExportOptions::default()
+            },
+            ExportOptions {
+                include_files: false,
+                ..ExportOptions::default()
+            },
+        ];
+        for format in [ExportFormat::Json, ExportFormat::Ndjson, ExportFormat::Markdown] {
+            for variant in &variants {
+                let options = ExportOptions {
+                    format,
+                    ..variant.clone()
+                };
+                let actual = match format {
+                    ExportFormat::Json => export_json(&profile, &options).unwrap().len(),
+                    ExportFormat::Ndjson => export_ndjson(&profile, &options).unwrap().len(),
+                    _ => export_markdown(&profile, &options).len(),
+                };
+                assert_eq!(estimate_export_size(&profile, &options), actual, "{:?}", options);
+            }
+        }
+    }
+
+    /// Minimal draft-07 validator covering the keywords used by `export_json_schema`
+    fn validate(
+        value: &serde_json::Value,
+        schema: &serde_json::Value,
+        root: &serde_json::Value,
+    ) -> Result<(), String> {
+        use serde_json::Value;
+
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.trim_start_matches("#/definitions/");
+            return validate(value, &root["definitions"][name], root);
+        }
+
+        if let Some(options) = schema.get("oneOf").and_then(Value::as_array) {
+            let matches = options.iter().filter(|o| validate(value, o, root).is_ok()).count();
+            return if matches == 1 {
+                Ok(())
+            } else {
+                Err(format!("{} matched {} oneOf branches", value, matches))
+            };
+        }
+
+        if let Some(expected) = schema.get("type") {
+            let types: Vec<&str> = match expected {
+                Value::Array(types) => types.iter().filter_map(Value::as_str).collect(),
+                other => vec![other.as_str().unwrap()],
+            };
+            let matches = types.iter().any(|t| match *t {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "boolean" => value.is_boolean(),
+                "null" => value.is_null(),
+                "number" => value.is_number(),
+                "integer" => value.is_i64() || value.is_u64(),
+                _ => false,
+            });
+            if !matches {
+                return Err(format!("{} is not of type {:?}", value, types));
+            }
+        }
+
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64) {
+            if value.as_f64().is_some_and(|v| v < minimum) {
+                return Err(format!("{} is below minimum {}", value, minimum));
+            }
+        }
+
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for item in array {
+                validate(item, items, root)?;
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for required in schema.get("required").and_then(Value::as_array).into_iter().flatten() {
+                let key = required.as_str().unwrap();
+                if !object.contains_key(key) {
+                    return Err(format!("missing required property `{}`", key));
+                }
+            }
+            for (key, field) in object {
+                match (properties.and_then(|p| p.get(key)), schema.get("additionalProperties")) {
+                    (Some(property), _) => validate(field, property, root)?,
+                    (None, Some(Value::Bool(false))) => {
+                        return Err(format!("unexpected property `{}`", key))
+                    }
+                    (None, Some(additional)) if additional.is_object() => {
+                        validate(field, additional, root)?
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_export_json_schema() {
+        let schema: serde_json::Value = serde_json::from_str(&export_json_schema()).unwrap();
+        assert_eq!(schema["$schema"], "http://json-schema.org/draft-07/schema#");
+
+        let profile = GrowthProfile::new();
+        let json = export_json(&profile, &ExportOptions::default()).unwrap();
+        let fresh: serde_json::Value = serde_json::from_str(&json).unwrap();
+        validate(&fresh, &schema, &schema).unwrap();
+
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        session.track_keystroke_timing = true;
+        session.record_keystroke();
+        session.record_file_edit("src/main.rs".to_string(), "rust".to_string());
+        session.add_commit(crate::session::CommitRef::new(
+            "abc123".to_string(),
+            "Test".to_string(),
+            Utc::now(),
+            vec!["src/main.rs".to_string()],
+        ));
+        session.mark_idle();
+        session.end();
+        profile.add_session(session);
+        profile.add_session(crate::session::Session::new(2));
+
+        let json = export_json(&profile, &ExportOptions::default()).unwrap();
+        let populated: serde_json::Value = serde_json::from_str(&json).unwrap();
+        validate(&populated, &schema, &schema).unwrap();
+
+        let mut invalid = populated.clone();
+        invalid["sessions"][0]["session"]["unknown_field"] = json!(1);
+        assert!(validate(&invalid, &schema, &schema).is_err());
+
+        profile.set_daily_cap(Some(8 * 3_600_000));
+        let options = ExportOptions {
+            duration_format: DurationFormat::Iso8601,
+            ..ExportOptions::default()
+        };
+        let json = export_json(&profile, &options).unwrap();
+        let with_durations: serde_json::Value = serde_json::from_str(&json).unwrap();
+        validate(&with_durations, &schema, &schema).unwrap();
+        let session = &with_durations["sessions"][0]["session"];
+        assert!(session["active_time_duration"].is_string());
+        assert!(session["idle_periods"][0]["duration"].is_string());
+        assert!(session.get("burst_window_duration").is_none());
+        assert!(!json.contains("duration_duration"));
+    }
+
     #[test]
     fn test_export_markdown() {
         let profile = GrowthProfile::new();
@@ -201,6 +1484,511 @@ mod tests {
         let md = export_markdown(&profile, &options);
         assert!(md.contains("Learning Growth Report"));
         assert!(md.contains(&profile.id));
+        assert!(md.contains("0 days max, 0 high, 0 moderate, 0 light, 84 inactive"));
+        assert!(md.contains(&format!(
+            "**Tracking Since:** {} (0 days)",
+            profile.created_at.format("%Y-%m-%d")
+        )));
+    }
+
+    #[test]
+    fn test_weekly_digest() {
+        let mut profile = GrowthProfile::new();
+        let today = Utc::now().date_naive();
+
+        let mut this_week = crate::storage::DailyAggregate::new(today - Duration::days(1));
+        this_week.total_time_ms = 3 * 3600 * 1000;
+        this_week.sessions_count = 1;
+        this_week.languages.insert("rust".to_string(), 1000);
+        let mut last_week = crate::storage::DailyAggregate::new(today - Duration::days(9));
+        last_week.total_time_ms = 3600 * 1000;
+        last_week.sessions_count = 1;
+        profile.daily_aggregates.push(this_week);
+        profile.daily_aggregates.push(last_week);
+
+        let digest = weekly_digest(&profile);
+        assert!(digest.contains("Total time: 3h 0m (+2h 0m vs last week)"));
+        assert!(digest.contains("Days active: 1/7"));
+        assert!(digest.contains("Top language: rust"));
+    }
+
+    #[test]
+    fn test_weekly_digest_no_activity() {
+        let profile = GrowthProfile::new();
+        let digest = weekly_digest(&profile);
+        assert!(digest.contains("No coding activity this week."));
+        assert!(digest.contains("+0h 0m vs last week"));
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1234567, NumberStyle::Grouped), "1,234,567");
+        assert_eq!(format_number(123, NumberStyle::Grouped), "123");
+        assert_eq!(format_number(1000, NumberStyle::Grouped), "1,000");
+        assert_eq!(format_number(1234567, NumberStyle::Plain), "1234567");
+    }
+
+    #[test]
+    fn test_export_markdown_busiest_commit_day() {
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        let friday = DateTime::parse_from_rfc3339("2024-01-19T16:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        session.add_commit(crate::session::CommitRef::new(
+            "abc".to_string(),
+            "Deploy".to_string(),
+            friday,
+            Vec::new(),
+        ));
+        profile.add_session(session);
+
+        let md = export_markdown(&profile, &ExportOptions::default());
+        assert!(md.contains("## Commits"));
+        assert!(md.contains("**Busiest Commit Day:** Friday (1 commits)"));
+        assert!(md.contains("**Polyglot Commits:** 0.0%"));
+    }
+
+    #[test]
+    fn test_export_markdown_top_files() {
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        for (file, edits) in [("src/lib.rs", 1), ("src/main.rs", 3), ("README.md", 2)] {
+            for _ in 0..edits {
+                session.record_file_edit(file.to_string(), "rust".to_string());
+            }
+        }
+        profile.add_session(session);
+
+        let mut options = ExportOptions {
+            top_files: Some(2),
+            ..ExportOptions::default()
+        };
+        let md = export_markdown(&profile, &options);
+        let main = md.find("1. `src/main.rs`: 0h 0m (50.0%)").unwrap();
+        let readme = md.find("2. `README.md`").unwrap();
+        assert!(main < readme);
+        assert!(!md.contains("src/lib.rs"));
+
+        options.anonymize = true;
+        let md = export_markdown(&profile, &options);
+        assert!(md.contains("1. `file-1.rs`"));
+        assert!(md.contains("2. `file-2.md`"));
+        assert!(!md.contains("src/main.rs"));
+
+        options.include_files = false;
+        assert!(!export_markdown(&profile, &options).contains("Most Active Files"));
+    }
+
+    #[test]
+    fn test_export_markdown_skips_non_counting_sessions() {
+        let mut profile = GrowthProfile::new();
+        let mut counted = crate::session::Session::new(1);
+        counted.record_file_edit("src/main.rs".to_string(), "rust".to_string());
+        profile.add_session(counted);
+
+        let mut demo = crate::session::Session::new(2);
+        demo.counts_toward_stats = false;
+        demo.record_file_edit("demo.rs".to_string(), "rust".to_string());
+        demo.record_file_edit("demo.rs".to_string(), "rust".to_string());
+        demo.record_file_edit("demo.rs".to_string(), "rust".to_string());
+        profile.add_session(demo);
+
+        let options = ExportOptions {
+            top_files: Some(5),
+            ..ExportOptions::default()
+        };
+        let md = export_markdown(&profile, &options);
+        assert!(md.contains("Total sessions recorded: 1\n"));
+        assert!(md.contains("1. `src/main.rs`: 0h 0m (100.0%)"));
+        assert!(!md.contains("demo.rs"));
+    }
+
+    #[test]
+    fn test_export_markdown_language_file_share() {
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("README.md".to_string(), "markdown".to_string());
+        profile.add_session(session);
+
+        let md = export_markdown(&profile, &ExportOptions::default());
+        assert!(md.contains("- **rust**: 0h (66.7% of time, 50.0% of files)"));
+    }
+
+    #[test]
+    fn test_count_paused_as_idle() {
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        let mut pause = crate::session::PausePeriod::new(Utc::now() - Duration::minutes(20));
+        pause.end(Utc::now() - Duration::minutes(10));
+        session.pause_periods.push(pause);
+        profile.add_session(session);
+
+        let default_options = ExportOptions::default();
+        let md = export_markdown(&profile, &default_options);
+        assert!(md.contains("- **Total Idle Time:** 0h 0m"));
+        let json = export_json(&profile, &default_options).unwrap();
+        let exported: GrowthProfile = serde_json::from_str(&json).unwrap();
+        assert!(exported.sessions[0].session.idle_periods.is_empty());
+
+        let options = ExportOptions {
+            count_paused_as_idle: true,
+            ..ExportOptions::default()
+        };
+        let md = export_markdown(&profile, &options);
+        assert!(md.contains("- **Total Idle Time:** 0h 10m"));
+        let json = export_json(&profile, &options).unwrap();
+        let exported: GrowthProfile = serde_json::from_str(&json).unwrap();
+        assert_eq!(exported.sessions[0].session.idle_periods.len(), 1);
+        assert!(exported.sessions[0].session.pause_periods.is_empty());
+    }
+
+    #[test]
+    fn test_commit_message_redaction() {
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        session.add_commit(crate::session::CommitRef::new(
+            "abc1234def".to_string(),
+            "Fix login crash (PROJ-4821), see secret-token".to_string(),
+            Utc::now(),
+            vec![],
+        ));
+        profile.add_session(session);
+
+        let options = ExportOptions {
+            commit_message_redactions: vec!["PROJ-\\d+".to_string(), "secret-token".to_string()],
+            ..ExportOptions::default()
+        };
+        let json = export_json(&profile, &options).unwrap();
+        assert!(json.contains("Fix login crash ([redacted]), see [redacted]"));
+        assert!(!json.contains("4821"));
+        assert!(export_json(&profile, &ExportOptions::default()).unwrap().contains("PROJ-4821"));
+
+        let redact_with = |text: &str, pattern: &str| {
+            let compiled = compile_redactions(&[pattern.to_string()]).unwrap();
+            redact(text, compiled.as_ref())
+        };
+        assert_eq!(redact_with("a1 b22 c", "\\w\\d+"), "[redacted] [redacted] c");
+        assert_eq!(redact_with("a.b", "a.b"), "[redacted]");
+        assert_eq!(redact_with("unchanged", ""), "unchanged");
+        assert_eq!(redact_with("keep a", "x*"), "keep a");
+    }
+
+    #[test]
+    fn test_commit_message_redaction_metacharacters() {
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        session.add_commit(crate::session::CommitRef::new(
+            "abc1234def".to_string(),
+            "Closes ABC-12 and PROJ-4821".to_string(),
+            Utc::now(),
+            vec![],
+        ));
+        profile.add_session(session);
+
+        for pattern in ["[A-Z]+-\\d+", "(ABC|PROJ)-\\d{2,4}"] {
+            let options = ExportOptions {
+                commit_message_redactions: vec![pattern.to_string()],
+                ..ExportOptions::default()
+            };
+            let json = export_json(&profile, &options).unwrap();
+            assert!(json.contains("Closes [redacted] and [redacted]"), "{}", pattern);
+        }
+
+        // An invalid pattern fails the export instead of leaking the message
+        let options = ExportOptions {
+            commit_message_redactions: vec!["[A-Z".to_string()],
+            ..ExportOptions::default()
+        };
+        let err = export_json(&profile, &options).unwrap_err();
+        assert!(err.to_string().contains("invalid redaction pattern `[A-Z`"));
+        let err = export_ndjson(&profile, &options).unwrap_err();
+        assert!(err.contains("invalid redaction pattern `[A-Z`"));
+    }
+
+    #[test]
+    fn test_export_json_embed_derived() {
+        let mut profile = GrowthProfile::new();
+        let mut session = crate::session::Session::new(1);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        profile.add_session(session);
+
+        let json = export_json(&profile, &ExportOptions::default()).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert!(value.get("derived").is_none());
+
+        let options = ExportOptions {
+            embed_derived: true,
+            ..ExportOptions::default()
+        };
+        let json = export_json(&profile, &options).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        let derived = &value["derived"];
+        assert!(derived["badge_svg"].as_str().unwrap().contains("<svg"));
+        assert!(derived["badge_url"].as_str().unwrap().starts_with("https://img.shields.io"));
+        assert_eq!(derived["languages"][0]["language"], "rust");
+        assert_eq!(derived["summary"]["total_sessions"], 1);
+
+        let schema: serde_json::Value = serde_json::from_str(&export_json_schema()).unwrap();
+        validate(&value, &schema, &schema).unwrap();
+    }
+
+    #[test]
+    fn test_duration_formats() {
+        let ms = (3600 + 23 * 60) * 1000;
+        assert_eq!(iso8601_duration(ms), "PT1H23M");
+        assert_eq!(iso8601_duration(45_500), "PT45S");
+        assert_eq!(iso8601_duration(0), "PT0S");
+        assert_eq!(iso8601_duration(999), "PT0S");
+        assert_eq!(iso8601_duration(2 * 3_600_000 + 5_000), "PT2H5S");
+
+        let mut profile = GrowthProfile::new();
+        profile.lifetime_stats.total_time_ms = ms;
+
+        let json = export_json(&profile, &ExportOptions::default()).unwrap();
+        assert!(!json.contains("_duration"));
+
+        let options = ExportOptions {
+            duration_format: DurationFormat::Iso8601,
+            ..ExportOptions::default()
+        };
+        let json = export_json(&profile, &options).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["lifetime_stats"]["total_time_ms"], ms);
+        assert_eq!(value["lifetime_stats"]["total_time_duration"], "PT1H23M");
+        assert!(export_markdown(&profile, &options).contains("**Total Active Time:** PT1H23M\n"));
+
+        let options = ExportOptions {
+            duration_format: DurationFormat::Seconds,
+            ..ExportOptions::default()
+        };
+        assert!(export_markdown(&profile, &options).contains("**Total Active Time:** 4980s"));
+    }
+
+    #[test]
+    fn test_fun_equivalents() {
+        let mut profile = GrowthProfile::new();
+        profile.lifetime_stats.total_time_ms = 260 * 60 * 1000;
+
+        let equivalents = fun_equivalents(&profile);
+        assert_eq!(equivalents["pomodoros"], 260 / 25);
+        assert_eq!(equivalents["coffees"], 2);
+        assert_eq!(equivalents["workdays"], 0);
+
+        assert!(!export_markdown(&profile, &ExportOptions::default()).contains("Fun Equivalents"));
+        let options = ExportOptions {
+            fun_equivalents: true,
+            ..ExportOptions::default()
+        };
+        let md = export_markdown(&profile, &options);
+        assert!(md.contains("## Fun Equivalents"));
+        assert!(md.contains("- **pomodoros:** 10"));
+    }
+
+    #[test]
+    fn test_export_markdown_number_style() {
+        let mut profile = GrowthProfile::new();
+        profile.lifetime_stats.total_keystrokes = 1234567;
+
+        let grouped = ExportOptions {
+            number_style: NumberStyle::Grouped,
+            ..ExportOptions::default()
+        };
+        assert!(export_markdown(&profile, &grouped).contains("**Total Keystrokes:** 1,234,567"));
+
+        let plain = ExportOptions::default();
+        assert!(export_markdown(&profile, &plain).contains("**Total Keystrokes:** 1234567"));
+    }
+
+    #[test]
+    fn test_export_heatmap_svg_week_bounds() {
+        let profile = GrowthProfile::new();
+
+        let svg = export_heatmap_svg(&profile, 0);
+        assert!(svg.starts_with("<svg width=\"54\""));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("rx=\"2\"").count(), 7);
+
+        let svg = export_heatmap_svg(&profile, 53);
+        assert!(svg.starts_with("<svg width=\"782\""));
+        assert_eq!(svg.matches("rx=\"2\"").count(), 53 * 7);
+    }
+
+    #[test]
+    fn test_export_heatmap_svg_streak_highlight() {
+        let mut profile = GrowthProfile::new();
+        for days_ago in [0, 1, 2, 5] {
+            let mut session = crate::session::Session::new(days_ago as u64);
+            session.started_at = Utc::now() - Duration::days(days_ago);
+            profile.add_session(session);
+        }
+        assert_eq!(profile.lifetime_stats.current_streak, 3);
+
+        let svg = export_heatmap_svg_with_streak(&profile, 4, true);
+        assert_eq!(svg.matches("stroke=").count(), 3);
+
+        assert!(!export_heatmap_svg(&profile, 4).contains("stroke="));
+    }
+
+    #[test]
+    fn test_generate_contributions_card_svg() {
+        let mut profile = GrowthProfile::new();
+        for days_ago in [0, 1, 3] {
+            let mut session = crate::session::Session::new(days_ago as u64);
+            session.started_at = Utc::now() - Duration::days(days_ago);
+            session.active_time_ms = 30 * 60 * 1000;
+            profile.add_session(session);
+        }
+
+        let svg = generate_contributions_card_svg(&profile, 4);
+        assert!(svg.starts_with("<svg width=\"360\""));
+        assert!(svg.ends_with("</svg>"));
+        assert_eq!(svg.matches("<svg").count(), 2);
+        assert!(svg.contains("3 active days in the last 4 weeks"));
+        assert!(svg.contains("Current streak: 2 days"));
+        assert!(svg.contains("Longest streak: 2 days"));
+        assert_eq!(svg.matches("class=\"legend\"").count(), 5);
+        let legend: Vec<&str> = svg.split("class=\"legend\"").skip(1).collect();
+        let colors = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+        for (swatch, color) in legend.iter().zip(colors) {
+            assert!(swatch.contains(&format!("fill=\"{}\"", color)));
+        }
+        // Heatmap cells plus the legend swatches
+        assert_eq!(svg.matches("rx=\"2\"").count(), 4 * 7 + 5);
+    }
+
+    #[test]
+    fn test_render_ascii_heatmap() {
+        let empty = render_ascii_heatmap(&GrowthProfile::new(), 4);
+        let lines: Vec<&str> = empty.lines().collect();
+        assert_eq!(lines.len(), 7);
+        assert!(lines.iter().all(|line| line.chars().count() == 4));
+        assert!(empty.chars().all(|c| c == ' ' || c == '\n'));
+
+        let mut profile = GrowthProfile::new();
+        let today = Utc::now().date_naive();
+        let mut busy = crate::storage::DailyAggregate::new(today);
+        busy.total_time_ms = 60 * 60 * 1000;
+        let mut light = crate::storage::DailyAggregate::new(today - Duration::days(1));
+        light.total_time_ms = 10 * 60 * 1000;
+        profile.daily_aggregates.push(busy);
+        profile.daily_aggregates.push(light);
+
+        let rendered = render_ascii_heatmap(&profile, 4);
+        assert_eq!(rendered.lines().count(), 7);
+        assert_eq!(rendered.matches('█').count(), 1);
+        assert_eq!(rendered.matches('░').count(), 1);
+    }
+
+    #[test]
+    fn test_export_heatmap_csv() {
+        let mut profile = GrowthProfile::new();
+        let today = Utc::now().date_naive();
+        let mut daily = crate::storage::DailyAggregate::new(today);
+        daily.total_time_ms = 45 * 60 * 1000;
+        profile.daily_aggregates.push(daily);
+
+        let csv = export_heatmap_csv(&profile, 2);
+        let mut lines = csv.lines();
+        assert_eq!(lines.next(), Some("date,minutes,level"));
+
+        let rows: Vec<&str> = lines.collect();
+        assert_eq!(rows.len(), generate_heatmap(&profile, 2).cells.len());
+        assert_eq!(rows.last(), Some(&format!("{},45,4", today.format("%Y-%m-%d")).as_str()));
+        let mut sorted = rows.clone();
+        sorted.sort();
+        assert_eq!(rows, sorted);
+    }
+
+    #[test]
+    fn test_generate_sparkline_svg() {
+        let mut profile = GrowthProfile::new();
+        let mut daily = crate::storage::DailyAggregate::new(Utc::now().date_naive());
+        daily.total_time_ms = 30 * 60 * 1000;
+        profile.daily_aggregates.push(daily);
+
+        let svg = generate_sparkline_svg(&profile, 14);
+        assert!(svg.starts_with("<svg width=\"56\" height=\"20\""));
+        assert!(svg.ends_with("</svg>"));
+
+        let points = svg.split("points=\"").nth(1).unwrap().split('"').next().unwrap();
+        let points: Vec<&str> = points.split(' ').collect();
+        assert_eq!(points.len(), 14);
+        assert_eq!(points[0], "2,18.0");
+        assert_eq!(points[13], "54,2.0");
+    }
+
+    #[test]
+    fn test_generate_hourly_barchart_svg() {
+        let bar_heights = |svg: &str| -> Vec<usize> {
+            svg.split("<rect class=\"bar\"")
+                .skip(1)
+                .map(|bar| {
+                    let height = bar.split("height=\"").nth(1).unwrap();
+                    height.split('"').next().unwrap().parse().unwrap()
+                })
+                .collect()
+        };
+
+        let empty = generate_hourly_barchart_svg(&GrowthProfile::new());
+        assert!(empty.ends_with("</svg>"));
+        let heights = bar_heights(&empty);
+        assert_eq!(heights.len(), 24);
+        assert!(heights.iter().all(|h| *h == 0));
+
+        let mut profile = GrowthProfile::new();
+        for (hour, minutes) in [(9, 30), (14, 90), (22, 10)] {
+            let mut session = crate::session::Session::new(hour);
+            let started = Utc::now().date_naive().and_hms_opt(hour as u32, 0, 0).unwrap();
+            session.started_at = started.and_utc();
+            session.active_time_ms = minutes * 60 * 1000;
+            profile.add_session(session);
+        }
+
+        let heights = bar_heights(&generate_hourly_barchart_svg(&profile));
+        assert_eq!(heights.len(), 24);
+        let tallest = (0..24).max_by_key(|&h| heights[h]).unwrap();
+        assert_eq!(tallest, 14);
+        assert_eq!(heights[14], 100);
+        assert!(heights[9] > heights[22]);
+    }
+
+    #[test]
+    fn test_generate_month_calendar_svg() {
+        let mut profile = GrowthProfile::new();
+        for (day, minutes) in [(5, 30), (20, 90)] {
+            let date = NaiveDate::from_ymd_opt(2024, 2, day).unwrap();
+            let mut daily = crate::storage::DailyAggregate::new(date);
+            daily.total_time_ms = minutes * 60 * 1000;
+            profile.daily_aggregates.push(daily);
+        }
+
+        let svg = generate_month_calendar_svg(&profile, 2024, 2);
+        assert!(svg.ends_with("</svg>"));
+        assert!(svg.contains("February 2024"));
+        assert_eq!(svg.matches("rx=\"2\"").count(), 29);
+        assert_eq!(svg.matches("fill=\"#ebedf0\"").count(), 27);
+
+        assert!(generate_month_calendar_svg(&profile, 2024, 0).is_empty());
+        assert!(generate_month_calendar_svg(&profile, 2024, 13).is_empty());
+    }
+
+    #[test]
+    fn test_generate_milestone_badge_svg() {
+        let mut profile = GrowthProfile::new();
+        let base = generate_milestone_badge_svg(&profile);
+        assert!(base.contains(">streak</text>"));
+        assert!(base.contains("fill=\"#4c1\""));
+        assert_eq!(base, generate_badge_svg(&profile));
+
+        profile.lifetime_stats.current_streak = 45;
+        let silver = generate_milestone_badge_svg(&profile);
+        assert!(silver.contains(">silver</text>"));
+        assert!(silver.contains(">45 days</text>"));
+        assert!(silver.contains("fill=\"#9f9f9f\""));
+        assert!(!silver.contains("#4c1"));
     }
 
     #[test]
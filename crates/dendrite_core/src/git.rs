@@ -1,8 +1,29 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 pub use crate::session::CommitRef;
-use crate::storage::GrowthProfile;
+use crate::storage::{local_date, GrowthProfile};
+
+/// Rough size of a commit, by number of files changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CommitSize {
+    Small,
+    Medium,
+    Large,
+}
+
+impl CommitSize {
+    /// Small: up to 2 files, Medium: 3-9 files, Large: 10 or more
+    pub fn from_files_changed(count: usize) -> Self {
+        match count {
+            0..=2 => CommitSize::Small,
+            3..=9 => CommitSize::Medium,
+            _ => CommitSize::Large,
+        }
+    }
+}
 
 /// Correlation between a commit and the session(s) it was made in
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -11,16 +32,45 @@ pub struct CommitCorrelation {
     pub session_id: u64,
     pub session_duration_ms: u64,
     pub files_in_common: Vec<String>,
+    pub files_changed_count: u32,
+    pub size: CommitSize,
 }
 
 /// Get all commit correlations from a profile
 pub fn get_commit_correlations(profile: &GrowthProfile) -> Vec<CommitCorrelation> {
+    correlate_commits(profile, None)
+}
+
+/// Get commit correlations, keeping only commits timestamped within the
+/// session's `started_at..=ended_at` window, widened by `tolerance_ms` each side
+pub fn get_commit_correlations_within(
+    profile: &GrowthProfile,
+    tolerance_ms: u64,
+) -> Vec<CommitCorrelation> {
+    correlate_commits(profile, Some(tolerance_ms))
+}
+
+fn correlate_commits(
+    profile: &GrowthProfile,
+    tolerance_ms: Option<u64>,
+) -> Vec<CommitCorrelation> {
     let mut correlations = Vec::new();
 
-    for stored_session in &profile.sessions {
+    for stored_session in profile.counted_sessions() {
         let session = &stored_session.session;
+        let window = tolerance_ms.map(|tolerance| {
+            let tolerance = Duration::milliseconds(tolerance as i64);
+            let end = session.ended_at.unwrap_or_else(Utc::now);
+            (session.started_at - tolerance, end + tolerance)
+        });
         
         for commit in &session.commits {
+            if let Some((start, end)) = window {
+                if commit.timestamp < start || commit.timestamp > end {
+                    continue;
+                }
+            }
+
             // Find files in common between session and commit
             let files_in_common: Vec<String> = session
                 .files_edited
@@ -34,6 +84,8 @@ pub fn get_commit_correlations(profile: &GrowthProfile) -> Vec<CommitCorrelation
                 session_id: session.id,
                 session_duration_ms: session.total_duration_ms(),
                 files_in_common,
+                files_changed_count: commit.files_changed.len() as u32,
+                size: CommitSize::from_files_changed(commit.files_changed.len()),
             });
         }
     }
@@ -41,11 +93,125 @@ pub fn get_commit_correlations(profile: &GrowthProfile) -> Vec<CommitCorrelation
     correlations
 }
 
+/// Average ms from session start to its first commit, across sessions with
+/// commits. Commits timestamped before the session started are ignored.
+pub fn time_to_first_commit(profile: &GrowthProfile) -> Option<u64> {
+    let offsets: Vec<u64> = profile
+        .counted_sessions()
+        .filter_map(|stored_session| {
+            let session = &stored_session.session;
+            session
+                .commits
+                .iter()
+                .filter(|c| c.timestamp >= session.started_at)
+                .map(|c| (c.timestamp - session.started_at).num_milliseconds() as u64)
+                .min()
+        })
+        .collect();
+
+    if offsets.is_empty() {
+        return None;
+    }
+
+    Some(offsets.iter().sum::<u64>() / offsets.len() as u64)
+}
+
+/// Count commits per weekday (index 0 = Monday, 6 = Sunday) in the
+/// profile's local time
+pub fn commits_by_weekday(profile: &GrowthProfile) -> [u32; 7] {
+    let mut counts = [0u32; 7];
+
+    for stored_session in profile.counted_sessions() {
+        for commit in &stored_session.session.commits {
+            let weekday = local_date(commit.timestamp, profile.utc_offset_minutes).weekday();
+            counts[weekday.num_days_from_monday() as usize] += 1;
+        }
+    }
+
+    counts
+}
+
+/// Infer a language id from a file path's extension, using the same ids
+/// editors report for `record_file_edit`
+pub fn language_from_path(path: &str) -> Option<&'static str> {
+    let file_name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+    let (_, extension) = file_name.rsplit_once('.')?;
+    let language = match extension.to_lowercase().as_str() {
+        "ts" | "tsx" | "mts" | "cts" => "typescript",
+        "js" | "jsx" | "mjs" | "cjs" => "javascript",
+        "py" | "pyi" => "python",
+        "rs" => "rust",
+        "go" => "go",
+        "java" => "java",
+        "cs" => "csharp",
+        "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => "cpp",
+        "c" | "h" => "c",
+        "rb" => "ruby",
+        "swift" => "swift",
+        "kt" | "kts" => "kotlin",
+        _ => return None,
+    };
+    Some(language)
+}
+
+/// Fraction of commits whose changed files span more than one inferred
+/// language; files with unrecognized extensions are ignored
+pub fn polyglot_commit_ratio(profile: &GrowthProfile) -> f32 {
+    let mut total = 0u32;
+    let mut polyglot = 0u32;
+
+    for stored_session in profile.counted_sessions() {
+        for commit in &stored_session.session.commits {
+            total += 1;
+            let languages: HashSet<&str> = commit
+                .files_changed
+                .iter()
+                .filter_map(|f| language_from_path(f))
+                .collect();
+            if languages.len() > 1 {
+                polyglot += 1;
+            }
+        }
+    }
+
+    if total == 0 {
+        return 0.0;
+    }
+    polyglot as f32 / total as f32
+}
+
 /// Parse a commit from JSON string
 pub fn parse_commit_json(json: &str) -> Result<CommitRef, serde_json::Error> {
     serde_json::from_str(json)
 }
 
+/// Parse a commit from JSON string, validating its contents
+///
+/// Returns a descriptive message when the payload is malformed, the
+/// `timestamp` is not RFC3339, or the `hash` is empty.
+pub fn parse_commit_json_checked(json: &str) -> Result<CommitRef, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("invalid commit JSON: {}", e))?;
+
+    match value.get("timestamp").and_then(|t| t.as_str()) {
+        Some(timestamp) => {
+            DateTime::parse_from_rfc3339(timestamp).map_err(|e| {
+                format!("invalid timestamp `{}`: {} (expected RFC3339)", timestamp, e)
+            })?;
+        }
+        None => return Err("missing or non-string field `timestamp`".to_string()),
+    }
+
+    let commit: CommitRef =
+        serde_json::from_value(value).map_err(|e| format!("invalid commit: {}", e))?;
+
+    if commit.hash.trim().is_empty() {
+        return Err("commit `hash` must not be empty".to_string());
+    }
+
+    Ok(commit)
+}
+
 /// Create a commit reference from components
 pub fn create_commit_ref(
     hash: String,
@@ -96,4 +262,194 @@ mod tests {
         assert_eq!(correlations.len(), 1);
         assert_eq!(correlations[0].files_in_common.len(), 1);
     }
+
+    #[test]
+    fn test_commit_correlations_skip_non_counting() {
+        let mut profile = crate::storage::GrowthProfile::new();
+        let mut demo = Session::new(1);
+        demo.counts_toward_stats = false;
+        demo.add_commit(create_commit_ref(
+            "abc123".to_string(),
+            "Demo".to_string(),
+            Utc::now(),
+            Vec::new(),
+        ));
+        profile.add_session(demo);
+
+        assert!(get_commit_correlations(&profile).is_empty());
+        assert!(get_commit_correlations_within(&profile, 0).is_empty());
+    }
+
+    #[test]
+    fn test_commit_correlations_within_session_window() {
+        let mut profile = crate::storage::GrowthProfile::new();
+        let mut session = Session::new(1);
+        session.record_file_edit("src/main.rs".to_string(), "rust".to_string());
+        let start = session.started_at;
+        session.ended_at = Some(start + Duration::hours(1));
+
+        let files = vec!["src/main.rs".to_string()];
+        session.add_commit(create_commit_ref(
+            "inside".to_string(),
+            "During session".to_string(),
+            start + Duration::minutes(30),
+            files.clone(),
+        ));
+        session.add_commit(create_commit_ref(
+            "outside".to_string(),
+            "Days later".to_string(),
+            start + Duration::days(3),
+            files.clone(),
+        ));
+        session.add_commit(create_commit_ref(
+            "edge".to_string(),
+            "Just after".to_string(),
+            start + Duration::minutes(62),
+            files,
+        ));
+        profile.add_session(session);
+
+        assert_eq!(get_commit_correlations(&profile).len(), 3);
+
+        let strict = get_commit_correlations_within(&profile, 0);
+        assert_eq!(strict.len(), 1);
+        assert_eq!(strict[0].commit.hash, "inside");
+
+        let tolerant = get_commit_correlations_within(&profile, 5 * 60 * 1000);
+        assert_eq!(tolerant.len(), 2);
+    }
+
+    #[test]
+    fn test_commit_size_weighting() {
+        let mut profile = crate::storage::GrowthProfile::new();
+        let mut session = Session::new(1);
+
+        session.add_commit(create_commit_ref(
+            "typo".to_string(),
+            "Fix typo".to_string(),
+            Utc::now(),
+            vec!["README.md".to_string()],
+        ));
+        session.add_commit(create_commit_ref(
+            "feature".to_string(),
+            "Add feature".to_string(),
+            Utc::now(),
+            (0..10).map(|i| format!("src/module_{}.rs", i)).collect(),
+        ));
+        profile.add_session(session);
+
+        let correlations = get_commit_correlations(&profile);
+        assert_eq!(correlations[0].files_changed_count, 1);
+        assert_eq!(correlations[0].size, CommitSize::Small);
+        assert_eq!(correlations[1].files_changed_count, 10);
+        assert_eq!(correlations[1].size, CommitSize::Large);
+        assert_eq!(CommitSize::from_files_changed(5), CommitSize::Medium);
+    }
+
+    #[test]
+    fn test_time_to_first_commit() {
+        let mut profile = crate::storage::GrowthProfile::new();
+        assert_eq!(time_to_first_commit(&profile), None);
+
+        let commit_at = |timestamp: DateTime<Utc>| {
+            create_commit_ref("abc123".to_string(), "Test".to_string(), timestamp, Vec::new())
+        };
+
+        let mut first = Session::new(1);
+        let start = first.started_at;
+        first.add_commit(commit_at(start + chrono::Duration::minutes(30)));
+        first.add_commit(commit_at(start + chrono::Duration::minutes(10)));
+        first.add_commit(commit_at(start - chrono::Duration::minutes(5)));
+        profile.add_session(first);
+
+        let mut second = Session::new(2);
+        let start = second.started_at;
+        second.add_commit(commit_at(start + chrono::Duration::minutes(20)));
+        profile.add_session(second);
+
+        profile.add_session(Session::new(3));
+
+        assert_eq!(time_to_first_commit(&profile), Some(15 * 60 * 1000));
+    }
+
+    #[test]
+    fn test_time_to_first_commit_skips_non_counting() {
+        let mut profile = crate::storage::GrowthProfile::new();
+        let mut demo = Session::new(1);
+        demo.counts_toward_stats = false;
+        let commit_at = demo.started_at + chrono::Duration::minutes(5);
+        demo.add_commit(create_commit_ref(
+            "abc123".to_string(),
+            "Demo".to_string(),
+            commit_at,
+            Vec::new(),
+        ));
+        profile.add_session(demo);
+
+        assert_eq!(time_to_first_commit(&profile), None);
+    }
+
+    #[test]
+    fn test_commits_by_weekday() {
+        let mut profile = crate::storage::GrowthProfile::new();
+        let mut session = Session::new(1);
+        let commit_at = |timestamp: &str| {
+            create_commit_ref(
+                "abc123".to_string(),
+                "Test".to_string(),
+                DateTime::parse_from_rfc3339(timestamp).unwrap().with_timezone(&Utc),
+                Vec::new(),
+            )
+        };
+
+        // 2024-01-15 is a Monday, 2024-01-19 a Friday
+        session.add_commit(commit_at("2024-01-15T10:00:00Z"));
+        session.add_commit(commit_at("2024-01-19T16:00:00Z"));
+        session.add_commit(commit_at("2024-01-19T17:30:00Z"));
+        profile.add_session(session);
+
+        assert_eq!(commits_by_weekday(&profile), [1, 0, 0, 0, 2, 0, 0]);
+
+        // At +08:00 the 16:00 and 17:30 UTC Friday commits land on Saturday
+        profile.utc_offset_minutes = 8 * 60;
+        assert_eq!(commits_by_weekday(&profile), [1, 0, 0, 0, 0, 2, 0]);
+    }
+
+    #[test]
+    fn test_polyglot_commit_ratio() {
+        let mut profile = GrowthProfile::new();
+        assert_eq!(polyglot_commit_ratio(&profile), 0.0);
+
+        let mut session = Session::new(1);
+        let commit = |files: &[&str]| {
+            create_commit_ref(
+                "abc1234".to_string(),
+                "msg".to_string(),
+                Utc::now(),
+                files.iter().map(|f| f.to_string()).collect(),
+            )
+        };
+        session.add_commit(commit(&["src/lib.rs", "src/main.rs", "README.md"]));
+        session.add_commit(commit(&["src/lib.rs", "web/app.ts"]));
+        profile.add_session(session);
+
+        assert!((polyglot_commit_ratio(&profile) - 0.5).abs() < f32::EPSILON);
+        assert_eq!(language_from_path("web\\App.TSX"), Some("typescript"));
+        assert_eq!(language_from_path("Makefile"), None);
+    }
+
+    #[test]
+    fn test_parse_commit_json_checked() {
+        let valid = r#"{"hash":"abc123","short_hash":"abc123","message":"Test","timestamp":"2024-01-15T10:30:00Z","files_changed":[]}"#;
+        assert!(parse_commit_json_checked(valid).is_ok());
+
+        let bad_timestamp = r#"{"hash":"abc123","short_hash":"abc123","message":"Test","timestamp":"yesterday","files_changed":[]}"#;
+        let err = parse_commit_json_checked(bad_timestamp).unwrap_err();
+        assert!(err.contains("invalid timestamp `yesterday`"));
+        assert!(err.contains("RFC3339"));
+
+        let empty_hash = r#"{"hash":"","short_hash":"","message":"Test","timestamp":"2024-01-15T10:30:00Z","files_changed":[]}"#;
+        let err = parse_commit_json_checked(empty_hash).unwrap_err();
+        assert!(err.contains("hash"));
+    }
 }
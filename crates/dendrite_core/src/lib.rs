@@ -1,4 +1,6 @@
 use wasm_bindgen::prelude::*;
+use chrono::NaiveDate;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
 pub mod session;
@@ -6,23 +8,20 @@ pub mod storage;
 pub mod git;
 pub mod visualization;
 pub mod export;
+pub mod backend;
+pub mod events;
 
-use session::{Session, SessionState, CommitRef};
-use storage::{GrowthProfile, SessionStats, LifetimeStats};
-use visualization::{HeatmapData, LanguageStat};
-use export::{ExportOptions, ExportFormat};
+use session::{ActivityKind, Session, CommitRef, FileEdit};
+use storage::{GrowthProfile, SessionComparison, SessionStats, UnendedSessionPolicy};
+use export::ExportOptions;
+use events::{EventSink, ProfileEvent};
 
 // Global session registry for managing active sessions
 static mut SESSION_REGISTRY: Option<HashMap<u64, Session>> = None;
 static mut NEXT_SESSION_ID: u64 = 1;
 
 fn get_registry() -> &'static mut HashMap<u64, Session> {
-    unsafe {
-        if SESSION_REGISTRY.is_none() {
-            SESSION_REGISTRY = Some(HashMap::new());
-        }
-        SESSION_REGISTRY.as_mut().unwrap()
-    }
+    unsafe { (*std::ptr::addr_of_mut!(SESSION_REGISTRY)).get_or_insert_with(HashMap::new) }
 }
 
 fn get_next_id() -> u64 {
@@ -33,6 +32,32 @@ fn get_next_id() -> u64 {
     }
 }
 
+thread_local! {
+    // Optional JS listener for profile events
+    static EVENT_CALLBACK: RefCell<Option<js_sys::Function>> = const { RefCell::new(None) };
+}
+
+/// Forwards profile events to the registered JS callback as JSON strings
+struct JsEventSink;
+
+impl EventSink for JsEventSink {
+    fn emit(&self, event: &ProfileEvent) {
+        // Cloned out so the callback may itself replace or clear the listener
+        let callback = EVENT_CALLBACK.with(|cb| cb.borrow().clone());
+        if let (Some(callback), Ok(json)) = (callback, serde_json::to_string(event)) {
+            let _ = callback.call1(&JsValue::NULL, &JsValue::from_str(&json));
+        }
+    }
+}
+
+fn reserve_id(id: u64) {
+    unsafe {
+        if id >= NEXT_SESSION_ID {
+            NEXT_SESSION_ID = id + 1;
+        }
+    }
+}
+
 // ============================================
 // Session Management
 // ============================================
@@ -59,6 +84,39 @@ pub fn record_file_edit(handle: u64, file_path: String, language: String) {
     }
 }
 
+/// Record a batch of file edits given as a JSON array of
+/// `{ "path", "language" }` objects
+#[wasm_bindgen]
+pub fn record_file_edits(handle: u64, edits_json: String) {
+    let edits: Vec<FileEdit> = match serde_json::from_str(&edits_json) {
+        Ok(e) => e,
+        Err(_) => return,
+    };
+
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.record_file_edits(edits);
+    }
+}
+
+/// Record a non-keystroke activity such as `"test_run"`, `"build"` or
+/// `"debug"`. Unknown kinds are ignored.
+#[wasm_bindgen]
+pub fn record_activity(
+    handle: u64,
+    kind: String,
+    file_path: Option<String>,
+    language: Option<String>,
+) {
+    let kind = match ActivityKind::parse(&kind) {
+        Some(k) => k,
+        None => return,
+    };
+
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.record_activity(kind, file_path, language);
+    }
+}
+
 #[wasm_bindgen]
 pub fn mark_idle(handle: u64) {
     if let Some(session) = get_registry().get_mut(&handle) {
@@ -73,12 +131,66 @@ pub fn resume_from_idle(handle: u64) {
     }
 }
 
+#[wasm_bindgen]
+pub fn coalesce_idle_periods(handle: u64, max_gap_ms: u64) {
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.coalesce_idle_periods(max_gap_ms);
+    }
+}
+
+#[wasm_bindgen]
+pub fn complete_pomodoro(handle: u64) {
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.complete_pomodoro();
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_keystroke_timing(handle: u64, enabled: bool) {
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.track_keystroke_timing = enabled;
+        if !enabled {
+            session.keystroke_timestamps.clear();
+        }
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_path_normalization(handle: u64, enabled: bool) {
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.normalize_paths = enabled;
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_session_project(handle: u64, project: Option<String>) {
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.project = project;
+    }
+}
+
+#[wasm_bindgen]
+pub fn set_session_counts(handle: u64, counts: bool) {
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.counts_toward_stats = counts;
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_keystroke_rhythm(handle: u64, bucket_ms: u64) -> String {
+    if let Some(session) = get_registry().get(&handle) {
+        let histogram = session.keystroke_interval_histogram(bucket_ms);
+        serde_json::to_string(&histogram).unwrap_or_default()
+    } else {
+        "{}".to_string()
+    }
+}
+
 #[wasm_bindgen]
 pub fn end_session(handle: u64) -> String {
     if let Some(session) = get_registry().get_mut(&handle) {
         session.end();
-        let stats = SessionStats::from_session(session);
-        serde_json::to_string(&stats).unwrap_or_default()
+        serde_json::to_string(&session.final_stats).unwrap_or_default()
     } else {
         "{}".to_string()
     }
@@ -94,6 +206,31 @@ pub fn get_active_session_stats(handle: u64) -> String {
     }
 }
 
+#[wasm_bindgen]
+pub fn compare_session_to_profile(handle: u64, profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    if let Some(session) = get_registry().get(&handle) {
+        let comparison = SessionComparison::compare(session, &profile);
+        serde_json::to_string(&comparison).unwrap_or_default()
+    } else {
+        "{}".to_string()
+    }
+}
+
+/// Compact live-update payload; compare `revision` to skip unchanged ticks
+#[wasm_bindgen]
+pub fn get_active_session_delta(handle: u64) -> String {
+    if let Some(session) = get_registry().get(&handle) {
+        serde_json::to_string(&session.delta()).unwrap_or_default()
+    } else {
+        "{}".to_string()
+    }
+}
+
 // ============================================
 // Storage Operations
 // ============================================
@@ -107,6 +244,29 @@ pub fn serialize_session(handle: u64) -> String {
     }
 }
 
+/// Put a serialized session back into the registry (e.g. after an editor
+/// reload). Reuses the session's own id when free, otherwise assigns a fresh
+/// handle. Returns 0 if the JSON cannot be parsed.
+#[wasm_bindgen]
+pub fn restore_session(session_json: String) -> u64 {
+    let mut session: Session = match serde_json::from_str(&session_json) {
+        Ok(s) => s,
+        Err(_) => return 0,
+    };
+
+    session.rehydrate();
+
+    let handle = if session.id == 0 || get_registry().contains_key(&session.id) {
+        get_next_id()
+    } else {
+        reserve_id(session.id);
+        session.id
+    };
+
+    get_registry().insert(handle, session);
+    handle
+}
+
 #[wasm_bindgen]
 pub fn save_session_to_profile(profile_json: String, session_json: String) -> String {
     let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
@@ -119,16 +279,124 @@ pub fn save_session_to_profile(profile_json: String, session_json: String) -> St
         Err(_) => return profile_json,
     };
 
-    profile.add_session(session);
+    events::add_session_notifying(&mut profile, session, &JsEventSink);
+    profile.to_json().unwrap_or(profile_json)
+}
+
+/// Like `save_session_to_profile`, but with `reject_unended` a session that
+/// was never ended is refused with `"error: <reason>"` instead of being ended
+#[wasm_bindgen]
+pub fn save_session_to_profile_checked(
+    profile_json: String,
+    session_json: String,
+    reject_unended: bool,
+) -> String {
+    let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(e) => return format!("error: invalid profile: {}", e),
+    };
+
+    let session: Session = match serde_json::from_str(&session_json) {
+        Ok(s) => s,
+        Err(e) => return format!("error: invalid session: {}", e),
+    };
+
+    let policy = if reject_unended {
+        UnendedSessionPolicy::Reject
+    } else {
+        UnendedSessionPolicy::End
+    };
+    let before = profile.lifetime_stats.clone();
+    match profile.add_session_with_policy(session, policy) {
+        Ok(()) => {
+            for event in events::streak_events(&before, &profile.lifetime_stats) {
+                JsEventSink.emit(&event);
+            }
+            profile.to_json().unwrap_or(profile_json)
+        }
+        Err(e) => format!("error: {}", e),
+    }
+}
+
+#[wasm_bindgen]
+pub fn upsert_session_to_profile(profile_json: String, session_json: String) -> String {
+    let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return profile_json,
+    };
+
+    let session: Session = match serde_json::from_str(&session_json) {
+        Ok(s) => s,
+        Err(_) => return profile_json,
+    };
+
+    profile.upsert_session(session);
     profile.to_json().unwrap_or(profile_json)
 }
 
+/// Register a callback invoked with an event JSON string (e.g.
+/// `{"type":"milestone_reached","days":30,"tier":"silver"}`) whenever saving a
+/// session extends or breaks the streak or crosses a milestone
+#[wasm_bindgen]
+pub fn set_event_callback(cb: js_sys::Function) {
+    EVENT_CALLBACK.with(|callback| *callback.borrow_mut() = Some(cb));
+}
+
+#[wasm_bindgen]
+pub fn clear_event_callback() {
+    EVENT_CALLBACK.with(|callback| *callback.borrow_mut() = None);
+}
+
 #[wasm_bindgen]
 pub fn create_empty_profile() -> String {
     let profile = GrowthProfile::new();
     profile.to_json().unwrap_or_else(|_| "{}".to_string())
 }
 
+/// Upgrade an older profile, returning `{"profile": ..., "notes": [...]}`
+/// or `{"error": "..."}`
+#[wasm_bindgen]
+pub fn migrate_profile(profile_json: String) -> String {
+    match storage::migrate_with_report(&profile_json) {
+        Ok((profile, notes)) => serde_json::json!({ "profile": profile, "notes": notes }).to_string(),
+        Err(e) => serde_json::json!({ "error": e }).to_string(),
+    }
+}
+
+/// Create an empty profile with a caller-supplied id and RFC3339 creation time
+#[wasm_bindgen]
+pub fn create_profile_with_id(id: String, created_at: String) -> String {
+    let created_at = match chrono::DateTime::parse_from_rfc3339(&created_at) {
+        Ok(t) => t.with_timezone(&chrono::Utc),
+        Err(_) => return "{}".to_string(),
+    };
+
+    let profile = GrowthProfile::new_with_id(id, created_at);
+    profile.to_json().unwrap_or_else(|_| "{}".to_string())
+}
+
+#[wasm_bindgen]
+pub fn compact_profile(profile_json: String) -> String {
+    let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return profile_json,
+    };
+
+    profile.compact();
+    profile.to_json().unwrap_or(profile_json)
+}
+
+/// Report what `compact_profile` would clean up, without returning the profile
+#[wasm_bindgen]
+pub fn get_compaction_report(profile_json: String) -> String {
+    let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    serde_json::to_string(&profile.compact()).unwrap_or_default()
+}
+
 #[wasm_bindgen]
 pub fn get_profile_stats(profile_json: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
@@ -139,166 +407,742 @@ pub fn get_profile_stats(profile_json: String) -> String {
     serde_json::to_string(&profile.lifetime_stats).unwrap_or_default()
 }
 
+/// Headline numbers for a profile overview
 #[wasm_bindgen]
-pub fn get_current_streak(profile_json: String) -> u32 {
+pub fn get_profile_summary(profile_json: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return 0,
+        Err(_) => return "{}".to_string(),
     };
 
-    profile.lifetime_stats.current_streak
+    export::profile_summary(&profile).to_string()
 }
 
+/// Implausible sessions as `[[session_id, reason], ...]`
 #[wasm_bindgen]
-pub fn get_longest_streak(profile_json: String) -> u32 {
+pub fn flag_anomalous_sessions(profile_json: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return 0,
+        Err(_) => return "[]".to_string(),
     };
 
-    profile.lifetime_stats.longest_streak
+    let flagged = storage::flag_anomalous_sessions(&profile);
+    serde_json::to_string(&flagged).unwrap_or_else(|_| "[]".to_string())
 }
 
-// ============================================
-// Git Integration
-// ============================================
-
 #[wasm_bindgen]
-pub fn add_commit_to_session(handle: u64, commit_json: String) {
-    let commit: CommitRef = match serde_json::from_str(&commit_json) {
-        Ok(c) => c,
-        Err(_) => return,
+pub fn get_average_interruptions_per_hour(profile_json: String) -> f32 {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return 0.0,
     };
 
-    if let Some(session) = get_registry().get_mut(&handle) {
-        session.add_commit(commit);
-    }
+    profile.average_interruptions_per_hour()
 }
 
 #[wasm_bindgen]
-pub fn get_commit_correlations(profile_json: String) -> String {
+pub fn get_longest_session(profile_json: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return "[]".to_string(),
+        Err(_) => return "null".to_string(),
     };
 
-    let correlations = git::get_commit_correlations(&profile);
-    serde_json::to_string(&correlations).unwrap_or_else(|_| "[]".to_string())
+    serde_json::to_string(&profile.longest_session()).unwrap_or_else(|_| "null".to_string())
 }
 
-// ============================================
-// Visualization
-// ============================================
-
+/// Stats for an inclusive `YYYY-MM-DD` date range
 #[wasm_bindgen]
-pub fn generate_heatmap(profile_json: String, weeks: u8) -> String {
+pub fn get_stats_for_range(profile_json: String, start: String, end: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
         Err(_) => return "{}".to_string(),
     };
 
-    let heatmap = visualization::generate_heatmap(&profile, weeks);
-    serde_json::to_string(&heatmap).unwrap_or_default()
+    let (start, end) = match (
+        NaiveDate::parse_from_str(&start, "%Y-%m-%d"),
+        NaiveDate::parse_from_str(&end, "%Y-%m-%d"),
+    ) {
+        (Ok(s), Ok(e)) => (s, e),
+        _ => return "{}".to_string(),
+    };
+
+    serde_json::to_string(&profile.stats_for_range(start, end)).unwrap_or_default()
 }
 
+/// Commits with an RFC3339 timestamp in the inclusive `start..=end` window
 #[wasm_bindgen]
-pub fn generate_hourly_distribution(profile_json: String) -> String {
+pub fn get_commits_in_range(profile_json: String, start: String, end: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return "{}".to_string(),
+        Err(_) => return "[]".to_string(),
     };
 
-    let hourly = visualization::generate_hourly_distribution(&profile);
-    serde_json::to_string(&hourly).unwrap_or_default()
+    let (start, end) = match (
+        chrono::DateTime::parse_from_rfc3339(&start),
+        chrono::DateTime::parse_from_rfc3339(&end),
+    ) {
+        (Ok(s), Ok(e)) => (s.with_timezone(&chrono::Utc), e.with_timezone(&chrono::Utc)),
+        _ => return "[]".to_string(),
+    };
+
+    serde_json::to_string(&profile.commits_in_range(start, end))
+        .unwrap_or_else(|_| "[]".to_string())
 }
 
+/// Active dates as a sorted array of `YYYY-MM-DD` strings
 #[wasm_bindgen]
-pub fn generate_language_breakdown(profile_json: String) -> String {
+pub fn get_active_dates(profile_json: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
         Err(_) => return "[]".to_string(),
     };
 
-    let breakdown = visualization::generate_language_breakdown(&profile);
-    serde_json::to_string(&breakdown).unwrap_or_else(|_| "[]".to_string())
+    serde_json::to_string(&profile.active_dates()).unwrap_or_else(|_| "[]".to_string())
 }
 
 #[wasm_bindgen]
-pub fn get_daily_aggregates(profile_json: String, days: u32) -> String {
+pub fn get_current_streak(profile_json: String) -> u32 {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return "[]".to_string(),
+        Err(_) => return 0,
     };
 
-    let aggregates = visualization::get_daily_aggregates(&profile, days);
-    serde_json::to_string(&aggregates).unwrap_or_else(|_| "[]".to_string())
+    profile.lifetime_stats.current_streak
 }
 
-// ============================================
-// Export
-// ============================================
-
 #[wasm_bindgen]
-pub fn export_json(profile_json: String, options_json: String) -> String {
+pub fn get_longest_streak(profile_json: String) -> u32 {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return "{}".to_string(),
+        Err(_) => return 0,
     };
 
-    let options: ExportOptions = match serde_json::from_str(&options_json) {
-        Ok(o) => o,
-        Err(_) => ExportOptions::default(),
+    profile.lifetime_stats.longest_streak
+}
+
+#[wasm_bindgen]
+pub fn rebucket_daily_aggregates(profile_json: String, utc_offset_minutes: i32) -> String {
+    let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return profile_json,
     };
 
-    export::export_json(&profile, &options).unwrap_or_default()
+    profile.rebucket_daily_aggregates(utc_offset_minutes);
+    profile.to_json().unwrap_or(profile_json)
 }
 
+/// Set (or clear, with `undefined`) the per-day active time cap
 #[wasm_bindgen]
-pub fn export_markdown(profile_json: String, options_json: String) -> String {
-    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+pub fn set_daily_cap(profile_json: String, cap_ms: Option<u64>) -> String {
+    let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return "# Error parsing profile\n".to_string(),
+        Err(_) => return profile_json,
     };
 
-    let options: ExportOptions = match serde_json::from_str(&options_json) {
-        Ok(o) => o,
-        Err(_) => ExportOptions::default(),
+    profile.set_daily_cap(cap_ms);
+    profile.to_json().unwrap_or(profile_json)
+}
+
+#[wasm_bindgen]
+pub fn grant_freeze_tokens(profile_json: String, count: u32) -> String {
+    let mut profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return profile_json,
     };
 
-    export::export_markdown(&profile, &options)
+    profile.grant_freeze_tokens(count);
+    profile.to_json().unwrap_or(profile_json)
 }
 
 #[wasm_bindgen]
-pub fn export_heatmap_svg(profile_json: String, weeks: u8) -> String {
+pub fn get_freeze_tokens(profile_json: String) -> u32 {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return String::new(),
+        Err(_) => return 0,
     };
 
-    export::export_heatmap_svg(&profile, weeks)
+    profile.freeze_tokens
 }
 
+/// How coding today could restore a streak that lapsed yesterday, or `"null"`
 #[wasm_bindgen]
-pub fn generate_badge_svg(profile_json: String) -> String {
+pub fn get_streak_recovery_hint(profile_json: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return String::new(),
+        Err(_) => return "null".to_string(),
     };
 
-    export::generate_badge_svg(&profile)
+    let hint = storage::streak_recovery_hint(&profile);
+    serde_json::to_string(&hint).unwrap_or_else(|_| "null".to_string())
 }
 
+/// Longest inactive stretch as `{"start", "end", "days"}`, or `"null"`
 #[wasm_bindgen]
-pub fn generate_badge_url(profile_json: String) -> String {
+pub fn get_longest_inactivity_gap(profile_json: String) -> String {
     let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
         Ok(p) => p,
-        Err(_) => return String::new(),
+        Err(_) => return "null".to_string(),
     };
 
-    export::generate_badge_url(&profile)
+    match storage::longest_inactivity_gap(&profile) {
+        Some((start, end, days)) => {
+            serde_json::json!({ "start": start, "end": end, "days": days }).to_string()
+        }
+        None => "null".to_string(),
+    }
 }
 
-// ============================================
+#[wasm_bindgen]
+pub fn get_goal_streak(profile_json: String, daily_goal_ms: u64) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let (current, longest) = storage::goal_streak(&profile, daily_goal_ms);
+    serde_json::json!({ "current": current, "longest": longest }).to_string()
+}
+
+#[wasm_bindgen]
+pub fn get_year_in_review(profile_json: String, year: i32) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let review = storage::year_in_review(&profile, year);
+    serde_json::to_string(&review).unwrap_or_default()
+}
+
+#[wasm_bindgen]
+pub fn get_weekly_goal_pace(profile_json: String, weekly_goal_ms: u64) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let pace = storage::weekly_goal_pace(&profile, weekly_goal_ms);
+    serde_json::to_string(&pace).unwrap_or_default()
+}
+
+// ============================================
+// Git Integration
+// ============================================
+
+#[wasm_bindgen]
+pub fn add_commit_to_session(handle: u64, commit_json: String) {
+    let commit: CommitRef = match serde_json::from_str(&commit_json) {
+        Ok(c) => c,
+        Err(_) => return,
+    };
+
+    if let Some(session) = get_registry().get_mut(&handle) {
+        session.add_commit(commit);
+    }
+}
+
+/// Like `add_commit_to_session`, but reports the outcome: `"ok"` on success,
+/// otherwise `"error: <reason>"` describing why the commit was rejected.
+#[wasm_bindgen]
+pub fn add_commit_to_session_checked(handle: u64, commit_json: String) -> String {
+    let commit = match git::parse_commit_json_checked(&commit_json) {
+        Ok(c) => c,
+        Err(e) => return format!("error: {}", e),
+    };
+
+    match get_registry().get_mut(&handle) {
+        Some(session) => {
+            session.add_commit(commit);
+            "ok".to_string()
+        }
+        None => format!("error: no session with handle {}", handle),
+    }
+}
+
+#[wasm_bindgen]
+pub fn get_commit_correlations(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let correlations = git::get_commit_correlations(&profile);
+    serde_json::to_string(&correlations).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_commit_correlations_within(profile_json: String, tolerance_ms: u64) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let correlations = git::get_commit_correlations_within(&profile, tolerance_ms);
+    serde_json::to_string(&correlations).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Average ms from session start to first commit, or `"null"` if none
+#[wasm_bindgen]
+pub fn get_time_to_first_commit(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "null".to_string(),
+    };
+
+    serde_json::to_string(&git::time_to_first_commit(&profile)).unwrap_or_else(|_| "null".to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_polyglot_commit_ratio(profile_json: String) -> f32 {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return 0.0,
+    };
+
+    git::polyglot_commit_ratio(&profile)
+}
+
+#[wasm_bindgen]
+pub fn get_commits_by_weekday(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let counts = git::commits_by_weekday(&profile);
+    serde_json::to_string(&counts).unwrap_or_else(|_| "[]".to_string())
+}
+
+// ============================================
+// Visualization
+// ============================================
+
+#[wasm_bindgen]
+pub fn generate_heatmap(profile_json: String, weeks: u8) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let heatmap = visualization::generate_heatmap(&profile, weeks);
+    serde_json::to_string(&heatmap).unwrap_or_default()
+}
+
+/// Update one day (`YYYY-MM-DD`) of a serialized heatmap and return it. Today
+/// is taken in local time at `utc_offset_minutes` from UTC, matching the
+/// profile the heatmap was generated from.
+#[wasm_bindgen]
+pub fn update_heatmap_cell(
+    heatmap_json: String,
+    date: String,
+    minutes: u32,
+    utc_offset_minutes: i32,
+) -> String {
+    let mut heatmap: visualization::HeatmapData = match serde_json::from_str(&heatmap_json) {
+        Ok(h) => h,
+        Err(_) => return heatmap_json,
+    };
+    let date = match NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+        Ok(d) => d,
+        Err(_) => return heatmap_json,
+    };
+
+    let today = storage::local_date(chrono::Utc::now(), utc_offset_minutes);
+    visualization::update_heatmap_cell(&mut heatmap, date, minutes, today);
+    serde_json::to_string(&heatmap).unwrap_or(heatmap_json)
+}
+
+#[wasm_bindgen]
+pub fn get_intensity_distribution(profile_json: String, weeks: u8) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let distribution = visualization::intensity_distribution(&profile, weeks);
+    serde_json::to_string(&distribution).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn generate_hourly_distribution(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let hourly = visualization::generate_hourly_distribution(&profile, profile.utc_offset_minutes);
+    serde_json::to_string(&hourly).unwrap_or_default()
+}
+
+/// Hourly distribution bucketed in local time at `offset_minutes` from UTC
+#[wasm_bindgen]
+pub fn generate_hourly_distribution_local(profile_json: String, offset_minutes: i32) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let hourly = visualization::generate_hourly_distribution(&profile, offset_minutes);
+    serde_json::to_string(&hourly).unwrap_or_default()
+}
+
+/// Score (0-1) for how productive an hour on a weekday (0 = Monday) has
+/// historically been; invalid weekdays score 0
+#[wasm_bindgen]
+pub fn get_productivity_forecast(profile_json: String, hour: u8, weekday_num: u8) -> f32 {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return 0.0,
+    };
+
+    match chrono::Weekday::try_from(weekday_num) {
+        Ok(weekday) => visualization::productivity_forecast(&profile, hour, weekday),
+        Err(_) => 0.0,
+    }
+}
+
+#[wasm_bindgen]
+pub fn generate_language_breakdown(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let breakdown = visualization::generate_language_breakdown(&profile);
+    serde_json::to_string(&breakdown).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn generate_language_breakdown_decayed(profile_json: String, half_life_days: f64) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let breakdown = visualization::generate_language_breakdown_decayed(&profile, half_life_days);
+    serde_json::to_string(&breakdown).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_language_usage_spans(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let spans = visualization::language_usage_spans(&profile);
+    serde_json::to_string(&spans).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_new_languages(profile_json: String, days: u32) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let languages = visualization::new_languages_in_period(&profile, days);
+    serde_json::to_string(&languages).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_session_quality_trend(profile_json: String, n: usize) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let points = visualization::session_quality_trend(&profile, n);
+    let slope = visualization::trend_slope(&points);
+    serde_json::json!({ "points": points, "slope": slope }).to_string()
+}
+
+#[wasm_bindgen]
+pub fn get_active_percentage_trend(profile_json: String, n: usize) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let points = visualization::active_percentage_trend(&profile, n);
+    let slope = visualization::trend_slope(&points);
+    serde_json::json!({ "points": points, "slope": slope }).to_string()
+}
+
+/// Smoothed active minutes for the last N days as `[["YYYY-MM-DD", minutes], ...]`
+#[wasm_bindgen]
+pub fn get_daily_minutes_ema(profile_json: String, days: u32, alpha: f32) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let series = visualization::daily_minutes_ema(&profile, days, alpha);
+    serde_json::to_string(&series).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// How unevenly active time is spread over the last N days (0 = even)
+#[wasm_bindgen]
+pub fn get_activity_gini(profile_json: String, days: u32) -> f32 {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return 0.0,
+    };
+
+    visualization::activity_gini(&profile, days)
+}
+
+/// Per-day active minutes for one project as `[["YYYY-MM-DD", minutes], ...]`;
+/// pass `"(none)"` for sessions without a project
+#[wasm_bindgen]
+pub fn get_project_daily_minutes(profile_json: String, project: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let series = visualization::project_daily_minutes(&profile, &project);
+    serde_json::to_string(&series).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_daily_aggregates(profile_json: String, days: u32) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "[]".to_string(),
+    };
+
+    let aggregates = visualization::get_daily_aggregates(&profile, days);
+    serde_json::to_string(&aggregates).unwrap_or_else(|_| "[]".to_string())
+}
+
+#[wasm_bindgen]
+pub fn get_daily_aggregate(profile_json: String, date: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "null".to_string(),
+    };
+
+    let date = match NaiveDate::parse_from_str(&date, "%Y-%m-%d") {
+        Ok(d) => d,
+        Err(_) => return "null".to_string(),
+    };
+
+    serde_json::to_string(&profile.daily_aggregate(date)).unwrap_or_else(|_| "null".to_string())
+}
+
+// ============================================
+// Export
+// ============================================
+
+/// Export the profile as JSON, or `{"error": "..."}` if the options are invalid
+#[wasm_bindgen]
+pub fn export_json(profile_json: String, options_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let options: ExportOptions = serde_json::from_str(&options_json).unwrap_or_default();
+
+    match export::export_json(&profile, &options) {
+        Ok(json) => json,
+        Err(e) => serde_json::json!({ "error": e.to_string() }).to_string(),
+    }
+}
+
+/// Export sessions as NDJSON, or a single `{"error": "..."}` line if the
+/// options are invalid
+#[wasm_bindgen]
+pub fn export_ndjson(profile_json: String, options_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    let options: ExportOptions = serde_json::from_str(&options_json).unwrap_or_default();
+
+    match export::export_ndjson(&profile, &options) {
+        Ok(ndjson) => ndjson,
+        Err(e) => format!("{}\n", serde_json::json!({ "error": e })),
+    }
+}
+
+#[wasm_bindgen]
+pub fn estimate_export_size(profile_json: String, options_json: String) -> u32 {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return 0,
+    };
+
+    let options: ExportOptions = serde_json::from_str(&options_json).unwrap_or_default();
+
+    export::estimate_export_size(&profile, &options).min(u32::MAX as usize) as u32
+}
+
+#[wasm_bindgen]
+pub fn export_json_schema() -> String {
+    export::export_json_schema()
+}
+
+#[wasm_bindgen]
+pub fn export_markdown(profile_json: String, options_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "# Error parsing profile\n".to_string(),
+    };
+
+    let options: ExportOptions = serde_json::from_str(&options_json).unwrap_or_default();
+
+    export::export_markdown(&profile, &options)
+}
+
+#[wasm_bindgen]
+pub fn get_fun_equivalents(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return "{}".to_string(),
+    };
+
+    let equivalents = export::fun_equivalents(&profile);
+    serde_json::to_string(&equivalents).unwrap_or_default()
+}
+
+#[wasm_bindgen]
+pub fn generate_weekly_digest(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::weekly_digest(&profile)
+}
+
+#[wasm_bindgen]
+pub fn export_heatmap_svg(profile_json: String, weeks: u8) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::export_heatmap_svg(&profile, weeks)
+}
+
+#[wasm_bindgen]
+pub fn generate_contributions_card_svg(profile_json: String, weeks: u8) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::generate_contributions_card_svg(&profile, weeks)
+}
+
+#[wasm_bindgen]
+pub fn export_heatmap_csv(profile_json: String, weeks: u8) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::export_heatmap_csv(&profile, weeks)
+}
+
+/// Export a ZIP bundle; errors (bad profile JSON, invalid options) are thrown
+/// to the caller as a message string
+#[wasm_bindgen]
+pub fn export_bundle(profile_json: String, options_json: String) -> Result<Vec<u8>, String> {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(e) => return Err(format!("invalid profile JSON: {}", e)),
+    };
+
+    let options: ExportOptions = serde_json::from_str(&options_json).unwrap_or_default();
+
+    export::export_bundle(&profile, &options)
+}
+
+#[wasm_bindgen]
+pub fn render_ascii_heatmap(profile_json: String, weeks: u8) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::render_ascii_heatmap(&profile, weeks)
+}
+
+#[wasm_bindgen]
+pub fn generate_sparkline_svg(profile_json: String, days: u32) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::generate_sparkline_svg(&profile, days)
+}
+
+#[wasm_bindgen]
+pub fn generate_hourly_barchart_svg(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::generate_hourly_barchart_svg(&profile)
+}
+
+#[wasm_bindgen]
+pub fn generate_month_calendar_svg(profile_json: String, year: i32, month: u32) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::generate_month_calendar_svg(&profile, year, month)
+}
+
+#[wasm_bindgen]
+pub fn export_heatmap_svg_with_streak(profile_json: String, weeks: u8) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::export_heatmap_svg_with_streak(&profile, weeks, true)
+}
+
+#[wasm_bindgen]
+pub fn generate_badge_svg(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::generate_badge_svg(&profile)
+}
+
+#[wasm_bindgen]
+pub fn generate_milestone_badge_svg(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::generate_milestone_badge_svg(&profile)
+}
+
+#[wasm_bindgen]
+pub fn generate_badge_url(profile_json: String) -> String {
+    let profile: GrowthProfile = match serde_json::from_str(&profile_json) {
+        Ok(p) => p,
+        Err(_) => return String::new(),
+    };
+
+    export::generate_badge_url(&profile)
+}
+
+// ============================================
 // Test and Utilities
 // ============================================
 
@@ -323,6 +1167,50 @@ mod tests {
         assert!(!stats.is_empty());
     }
 
+    #[test]
+    fn test_restore_session() {
+        let handle = init_session();
+        record_keystroke(handle);
+        let session_json = serialize_session(handle);
+        get_registry().remove(&handle);
+
+        let restored = restore_session(session_json);
+        assert_eq!(restored, handle);
+        record_keystroke(restored);
+        assert_eq!(get_registry().get(&restored).unwrap().keystroke_count, 2);
+
+        assert_eq!(restore_session("not json".to_string()), 0);
+    }
+
+    #[test]
+    fn test_compare_session_to_profile() {
+        let two_minutes_ago = chrono::Utc::now() - chrono::Duration::minutes(2);
+
+        let mut profile = GrowthProfile::new();
+        let mut baseline = Session::new(100);
+        baseline.started_at = two_minutes_ago;
+        baseline.keystroke_count = 100;
+        baseline.active_time_ms = 60_000;
+        baseline.end();
+        profile.add_session(baseline);
+        let profile_json = profile.to_json().unwrap();
+
+        let handle = init_session();
+        let empty = compare_session_to_profile(handle, create_empty_profile());
+        let empty: SessionComparison = serde_json::from_str(&empty).unwrap();
+        assert_eq!(empty.keystroke_density_diff, 0.0);
+
+        {
+            let session = get_registry().get_mut(&handle).unwrap();
+            session.started_at = two_minutes_ago;
+            session.keystroke_count = 120;
+            session.active_time_ms = 60_000;
+        }
+        let comparison = compare_session_to_profile(handle, profile_json);
+        let comparison: SessionComparison = serde_json::from_str(&comparison).unwrap();
+        assert!((comparison.keystroke_density_diff - 20.0).abs() < 0.01);
+    }
+
     #[test]
     fn test_profile_creation() {
         let profile_json = create_empty_profile();
@@ -331,4 +1219,35 @@ mod tests {
         let profile: GrowthProfile = serde_json::from_str(&profile_json).unwrap();
         assert!(!profile.id.is_empty());
     }
+
+    #[test]
+    fn test_add_commit_checked() {
+        let handle = init_session();
+
+        let ok = add_commit_to_session_checked(
+            handle,
+            r#"{"hash":"abc123","short_hash":"abc123","message":"Test","timestamp":"2024-01-15T10:30:00Z","files_changed":[]}"#.to_string(),
+        );
+        assert_eq!(ok, "ok");
+
+        let bad = add_commit_to_session_checked(
+            handle,
+            r#"{"hash":"abc123","short_hash":"abc123","message":"Test","timestamp":"15/01/2024","files_changed":[]}"#.to_string(),
+        );
+        assert!(bad.starts_with("error: invalid timestamp"));
+    }
+
+    #[test]
+    fn test_get_daily_aggregate() {
+        let handle = init_session();
+        let session_json = serialize_session(handle);
+        let profile_json = save_session_to_profile(create_empty_profile(), session_json);
+        let today = chrono::Utc::now().date_naive();
+
+        let daily = get_daily_aggregate(profile_json.clone(), today.format("%Y-%m-%d").to_string());
+        assert!(daily.contains("\"sessions_count\":1"));
+
+        assert_eq!(get_daily_aggregate(profile_json.clone(), "2000-01-01".to_string()), "null");
+        assert_eq!(get_daily_aggregate(profile_json, "not-a-date".to_string()), "null");
+    }
 }
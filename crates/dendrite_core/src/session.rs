@@ -1,20 +1,40 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, VecDeque};
+
+use crate::storage::SessionStats;
 
 /// Represents the state of a session
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum SessionState {
+    #[default]
     Active,
     Idle,
     Paused,
     Ended,
 }
 
-impl Default for SessionState {
-    fn default() -> Self {
-        SessionState::Active
+/// The kind of work an activity represents
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    Edit,
+    TestRun,
+    Build,
+    Debug,
+}
+
+impl ActivityKind {
+    /// Parse a kind name such as `"edit"` or `"test_run"`
+    pub fn parse(kind: &str) -> Option<Self> {
+        match kind.to_lowercase().as_str() {
+            "edit" => Some(ActivityKind::Edit),
+            "test_run" | "testrun" | "test" => Some(ActivityKind::TestRun),
+            "build" => Some(ActivityKind::Build),
+            "debug" => Some(ActivityKind::Debug),
+            _ => None,
+        }
     }
 }
 
@@ -37,10 +57,13 @@ impl IdlePeriod {
 
     pub fn end(&mut self, ended_at: DateTime<Utc>) {
         self.ended_at = Some(ended_at);
-        self.duration_ms = (ended_at - self.started_at).num_milliseconds() as u64;
+        self.duration_ms = (ended_at - self.started_at).num_milliseconds().max(0) as u64;
     }
 }
 
+/// A span during which the session was explicitly paused
+pub type PausePeriod = IdlePeriod;
+
 /// Reference to a git commit made during a session
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CommitRef {
@@ -64,60 +87,360 @@ impl CommitRef {
     }
 }
 
+/// Gaps between activity at or above this are not counted as active time
+pub const ACTIVE_GAP_THRESHOLD_MS: u64 = 5000;
+
+/// Gaps longer than this are recorded as an idle period covering the excess,
+/// for editors that never call `mark_idle`. Shorter pauses (reading, thinking)
+/// are neither active nor idle.
+pub const AUTO_IDLE_THRESHOLD_MS: u64 = 2 * 60 * 1000;
+
+/// Active time counts as engaged (real coding rather than reading with the
+/// editor open) when at least `ENGAGED_MIN_KEYSTROKES` counted keystrokes
+/// landed within the trailing `ENGAGED_WINDOW_MS`
+pub const ENGAGED_WINDOW_MS: i64 = 30_000;
+pub const ENGAGED_MIN_KEYSTROKES: usize = 10;
+
+/// Most recent keystroke timestamps kept when `track_keystroke_timing` is on;
+/// older ones are dropped so long sessions don't grow without bound
+pub const MAX_KEYSTROKE_TIMESTAMPS: usize = 10_000;
+
+fn default_burst_limit() -> u32 {
+    20
+}
+
+fn default_burst_window_ms() -> u64 {
+    100
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Order-independent fingerprint of a language time map: the wrapping sum of
+/// per-entry hashes, so one entry can be swapped out without rehashing the rest
+fn languages_fingerprint(languages: &HashMap<String, u64>) -> u64 {
+    languages
+        .iter()
+        .fold(0, |sum, (language, time)| sum.wrapping_add(language_fingerprint(language, *time)))
+}
+
+fn language_fingerprint(language: &str, time_ms: u64) -> u64 {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    (language, time_ms).hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Normalize a file path so the same file is recorded once: separators
+/// become `/`, `.` segments and a leading `./` are dropped, and `..`
+/// collapses the preceding segment where possible
+pub fn normalize_path(path: &str) -> String {
+    let unified = path.replace('\\', "/");
+    let absolute = unified.starts_with('/');
+
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in unified.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => match segments.last() {
+                Some(&last) if last != ".." => {
+                    segments.pop();
+                }
+                _ if absolute => {}
+                _ => segments.push(".."),
+            },
+            _ => segments.push(segment),
+        }
+    }
+
+    let joined = segments.join("/");
+    if absolute {
+        format!("/{}", joined)
+    } else {
+        joined
+    }
+}
+
+/// One entry in a batched file-edit report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FileEdit {
+    pub path: String,
+    pub language: String,
+}
+
+/// The small, frequently changing part of a session for live UI updates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionDelta {
+    pub revision: u64,
+    pub keystroke_count: u32,
+    pub active_time_ms: u64,
+    pub state: SessionState,
+    pub primary_language: Option<String>,
+}
+
 /// A tracked period of focused work
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: u64,
     pub started_at: DateTime<Utc>,
     pub ended_at: Option<DateTime<Utc>>,
+    /// Project (e.g. workspace name) the session belongs to, if known
+    #[serde(default)]
+    pub project: Option<String>,
+    /// When false the session is stored but left out of lifetime stats,
+    /// daily aggregates and streaks (pairing, demos)
+    #[serde(default = "default_true")]
+    pub counts_toward_stats: bool,
     pub active_time_ms: u64,
+    /// Portion of `active_time_ms` with sustained typing; see `ENGAGED_WINDOW_MS`
+    #[serde(default)]
+    pub engaged_time_ms: u64,
     pub keystroke_count: u32,
+    /// Every recorded keystroke, including those clamped as paste bursts
+    #[serde(default)]
+    pub raw_keystroke_count: u32,
+    /// Max keystrokes counted per `burst_window_ms`
+    #[serde(default = "default_burst_limit")]
+    pub burst_limit: u32,
+    #[serde(default = "default_burst_window_ms")]
+    pub burst_window_ms: u64,
+    /// Opt-in capture of per-keystroke timestamps for rhythm analysis
+    #[serde(default)]
+    pub track_keystroke_timing: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keystroke_timestamps: Vec<DateTime<Utc>>,
     pub files_edited: Vec<String>,
+    /// Apply `normalize_path` to edited file paths; disable if callers pre-normalize
+    #[serde(default = "default_true")]
+    pub normalize_paths: bool,
+    /// Latest language each edited file was recorded with
+    #[serde(default, serialize_with = "crate::storage::serialize_sorted")]
+    pub file_languages: HashMap<String, String>,
+    /// Time attributed to each edited file, in the same increments as `languages`
+    #[serde(default, serialize_with = "crate::storage::serialize_sorted")]
+    pub file_times: HashMap<String, u64>,
+    #[serde(serialize_with = "crate::storage::serialize_sorted")]
     pub languages: HashMap<String, u64>,
+    /// Active time attributed to each kind of activity
+    #[serde(default, serialize_with = "crate::storage::serialize_sorted")]
+    pub activity_times: HashMap<ActivityKind, u64>,
     pub idle_periods: Vec<IdlePeriod>,
+    /// Spans between `pause` and `resume`, kept apart from idle periods
+    #[serde(default)]
+    pub pause_periods: Vec<PausePeriod>,
     pub commits: Vec<CommitRef>,
+    #[serde(default)]
+    pub pomodoro_intervals: u32,
+    /// Stats frozen at the moment the session ended
+    #[serde(default)]
+    pub final_stats: Option<SessionStats>,
     #[serde(skip)]
     pub state: SessionState,
     #[serde(skip)]
     last_activity: DateTime<Utc>,
     #[serde(skip)]
     current_idle: Option<IdlePeriod>,
+    #[serde(skip)]
+    current_pause: Option<PausePeriod>,
+    #[serde(skip)]
+    burst_window_start: DateTime<Utc>,
+    #[serde(skip)]
+    burst_window_count: u32,
+    #[serde(skip)]
+    recent_keystrokes: VecDeque<DateTime<Utc>>,
+    /// Bumped on every change, so live views can skip unchanged polls
+    #[serde(skip)]
+    pub revision: u64,
+    /// Cached `primary_language`, kept current by `track_file`
+    #[serde(skip)]
+    cached_primary_language: Option<String>,
+    /// `languages_fingerprint` of the map the cache was built from; a mismatch
+    /// means `languages` was edited directly and the cache is stale
+    #[serde(skip)]
+    cached_languages_fingerprint: u64,
 }
 
 impl Session {
     pub fn new(id: u64) -> Self {
-        let now = Utc::now();
+        Self::new_at(id, Utc::now())
+    }
+
+    /// Create a session with a fixed start time, for reproducible fixtures and imports
+    pub fn new_at(id: u64, started_at: DateTime<Utc>) -> Self {
+        let now = started_at;
         Self {
             id,
             started_at: now,
             ended_at: None,
+            project: None,
+            counts_toward_stats: true,
             active_time_ms: 0,
+            engaged_time_ms: 0,
             keystroke_count: 0,
+            raw_keystroke_count: 0,
+            burst_limit: default_burst_limit(),
+            burst_window_ms: default_burst_window_ms(),
+            track_keystroke_timing: false,
+            keystroke_timestamps: Vec::new(),
             files_edited: Vec::new(),
+            normalize_paths: true,
+            file_languages: HashMap::new(),
+            file_times: HashMap::new(),
             languages: HashMap::new(),
+            activity_times: HashMap::new(),
             idle_periods: Vec::new(),
+            pause_periods: Vec::new(),
             commits: Vec::new(),
+            pomodoro_intervals: 0,
+            final_stats: None,
             state: SessionState::Active,
             last_activity: now,
             current_idle: None,
+            current_pause: None,
+            burst_window_start: now,
+            burst_window_count: 0,
+            recent_keystrokes: VecDeque::new(),
+            revision: 0,
+            cached_primary_language: None,
+            cached_languages_fingerprint: 0,
         }
     }
 
+    /// Restore runtime-only state after deserialization
+    pub fn rehydrate(&mut self) {
+        let now = Utc::now();
+        self.state = if self.ended_at.is_some() {
+            SessionState::Ended
+        } else {
+            SessionState::Active
+        };
+        self.last_activity = now;
+        self.current_idle = None;
+        self.current_pause = None;
+        self.burst_window_start = now;
+        self.burst_window_count = 0;
+        self.recent_keystrokes.clear();
+        self.refresh_primary_language();
+    }
+
     /// Record a keystroke in the session
+    ///
+    /// Keystrokes beyond `burst_limit` within one `burst_window_ms` window
+    /// (e.g. a large paste) only count towards `raw_keystroke_count`.
     pub fn record_keystroke(&mut self) {
-        self.keystroke_count += 1;
-        self.update_activity_time();
+        let now = Utc::now();
+        self.raw_keystroke_count += 1;
+
+        if self.track_keystroke_timing {
+            if self.keystroke_timestamps.len() >= MAX_KEYSTROKE_TIMESTAMPS {
+                let excess = self.keystroke_timestamps.len() + 1 - MAX_KEYSTROKE_TIMESTAMPS;
+                self.keystroke_timestamps.drain(..excess);
+            }
+            self.keystroke_timestamps.push(now);
+        }
+
+        // A negative elapsed time means the clock jumped backward; start a fresh
+        // window rather than waiting for the old one to expire
+        let elapsed_ms = (now - self.burst_window_start).num_milliseconds();
+        if elapsed_ms < 0 || elapsed_ms >= self.burst_window_ms as i64 {
+            self.burst_window_start = now;
+            self.burst_window_count = 0;
+        }
+
+        if self.burst_window_count < self.burst_limit {
+            self.burst_window_count += 1;
+            self.keystroke_count += 1;
+            self.recent_keystrokes.push_back(now);
+            self.trim_recent_keystrokes(now);
+        }
+
+        self.update_activity_time(ActivityKind::Edit);
     }
 
     /// Record a file edit
     pub fn record_file_edit(&mut self, file_path: String, language: String) {
+        self.record_file_activity(file_path, language, ActivityKind::Edit);
+    }
+
+    /// Record an activity of a given kind, optionally tied to a file
+    pub fn record_activity(
+        &mut self,
+        kind: ActivityKind,
+        file_path: Option<String>,
+        language: Option<String>,
+    ) {
+        match (file_path, language) {
+            (Some(file_path), Some(language)) => {
+                self.record_file_activity(file_path, language, kind)
+            }
+            _ => self.update_activity_time(kind),
+        }
+    }
+
+    /// Record several file edits with a single activity-time update
+    pub fn record_file_edits(&mut self, edits: Vec<FileEdit>) {
+        if edits.is_empty() {
+            return;
+        }
+        self.update_activity_time(ActivityKind::Edit);
+        for edit in edits {
+            self.track_file(edit.path, edit.language);
+        }
+    }
+
+    fn record_file_activity(&mut self, file_path: String, language: String, kind: ActivityKind) {
+        self.update_activity_time(kind);
+        self.track_file(file_path, language);
+    }
+
+    fn track_file(&mut self, file_path: String, language: String) {
+        let file_path = if self.normalize_paths {
+            normalize_path(&file_path)
+        } else {
+            file_path
+        };
         if !self.files_edited.contains(&file_path) {
-            self.files_edited.push(file_path);
+            self.files_edited.push(file_path.clone());
+        }
+        self.file_languages.insert(file_path.clone(), language.clone());
+
+        // Track time spent in this language and file
+        let language_time = self.languages.entry(language.clone()).or_insert(0);
+        let previous_time = *language_time;
+        *language_time += 1000; // 1 second increment
+        let language_time = *language_time;
+        *self.file_times.entry(file_path).or_insert(0) += 1000;
+
+        // Update the fingerprint for just this entry; any earlier direct edit
+        // still shows up as a mismatch when the cache is read
+        if previous_time > 0 {
+            self.cached_languages_fingerprint = self
+                .cached_languages_fingerprint
+                .wrapping_sub(language_fingerprint(&language, previous_time));
+        }
+        self.cached_languages_fingerprint = self
+            .cached_languages_fingerprint
+            .wrapping_add(language_fingerprint(&language, language_time));
+
+        // Only the language just incremented can overtake the cached leader
+        if self.cached_primary_language.as_ref() != Some(&language) {
+            let leader_time = self
+                .cached_primary_language
+                .as_ref()
+                .and_then(|lang| self.languages.get(lang))
+                .copied()
+                .unwrap_or(0);
+            if language_time > leader_time {
+                self.cached_primary_language = Some(language);
+            }
         }
-        self.update_activity_time();
-        
-        // Track time spent in this language
-        *self.languages.entry(language).or_insert(0) += 1000; // 1 second increment
+    }
+
+    /// Rebuild the cached primary language from `languages`
+    fn refresh_primary_language(&mut self) {
+        self.cached_primary_language = self.scan_primary_language();
+        self.cached_languages_fingerprint = languages_fingerprint(&self.languages);
     }
 
     /// Mark the session as idle
@@ -125,24 +448,48 @@ impl Session {
         if self.state == SessionState::Active {
             self.state = SessionState::Idle;
             self.current_idle = Some(IdlePeriod::new(Utc::now()));
+            self.revision += 1;
         }
     }
 
-    /// Resume from idle state
+    /// Resume from idle state. A resume without a matching `mark_idle` still
+    /// records any long gap since the last activity as an idle period.
     pub fn resume_from_idle(&mut self) {
-        if self.state == SessionState::Idle {
-            if let Some(mut idle) = self.current_idle.take() {
-                idle.end(Utc::now());
-                self.idle_periods.push(idle);
+        let now = Utc::now();
+        match self.state {
+            SessionState::Idle => {
+                if let Some(mut idle) = self.current_idle.take() {
+                    idle.end(now);
+                    self.idle_periods.push(idle);
+                }
             }
-            self.state = SessionState::Active;
-            self.last_activity = Utc::now();
+            SessionState::Active => self.record_auto_idle(now),
+            _ => return,
+        }
+        self.state = SessionState::Active;
+        self.last_activity = now;
+        self.revision += 1;
+    }
+
+    /// Record the part of the gap since `last_activity` beyond
+    /// `AUTO_IDLE_THRESHOLD_MS` as an idle period
+    fn record_auto_idle(&mut self, now: DateTime<Utc>) {
+        let gap = (now - self.last_activity).num_milliseconds().max(0) as u64;
+        if gap > AUTO_IDLE_THRESHOLD_MS {
+            let threshold = chrono::Duration::milliseconds(AUTO_IDLE_THRESHOLD_MS as i64);
+            let mut idle = IdlePeriod::new(self.last_activity + threshold);
+            idle.end(now);
+            self.idle_periods.push(idle);
         }
     }
 
     /// Pause the session manually
     pub fn pause(&mut self) {
+        if self.state != SessionState::Paused {
+            self.current_pause = Some(PausePeriod::new(Utc::now()));
+        }
         self.state = SessionState::Paused;
+        self.revision += 1;
     }
 
     /// Resume a paused session
@@ -150,6 +497,15 @@ impl Session {
         if self.state == SessionState::Paused {
             self.state = SessionState::Active;
             self.last_activity = Utc::now();
+            self.close_pause();
+            self.revision += 1;
+        }
+    }
+
+    fn close_pause(&mut self) {
+        if let Some(mut pause) = self.current_pause.take() {
+            pause.end(Utc::now());
+            self.pause_periods.push(pause);
         }
     }
 
@@ -163,17 +519,97 @@ impl Session {
             idle.end(Utc::now());
             self.idle_periods.push(idle);
         }
+        self.close_pause();
+
+        self.final_stats = Some(SessionStats::from_session(self));
+        self.revision += 1;
+    }
+
+    /// Merge consecutive idle periods separated by less than `max_gap_ms` of
+    /// activity into one, summing their durations. Overlapping periods count
+    /// the time they share once.
+    pub fn coalesce_idle_periods(&mut self, max_gap_ms: u64) {
+        self.idle_periods.sort_by_key(|p| p.started_at);
+
+        let mut merged: Vec<IdlePeriod> = Vec::with_capacity(self.idle_periods.len());
+        for period in self.idle_periods.drain(..) {
+            if let Some(last) = merged.last_mut() {
+                if let Some(last_end) = last.ended_at {
+                    let gap = (period.started_at - last_end).num_milliseconds();
+                    if gap < max_gap_ms as i64 {
+                        match period.ended_at {
+                            // Overlapping periods: only time past the current
+                            // end is new idle time
+                            Some(end) if gap < 0 => {
+                                if end > last_end {
+                                    last.duration_ms += (end - last_end).num_milliseconds() as u64;
+                                    last.ended_at = Some(end);
+                                }
+                            }
+                            _ => {
+                                last.ended_at = period.ended_at;
+                                last.duration_ms += period.duration_ms;
+                            }
+                        }
+                        continue;
+                    }
+                }
+            }
+            merged.push(period);
+        }
+
+        self.idle_periods = merged;
+        self.revision += 1;
     }
 
     /// Add a commit reference to this session
     pub fn add_commit(&mut self, commit: CommitRef) {
         self.commits.push(commit);
+        self.revision += 1;
+    }
+
+    /// Record a completed pomodoro focus interval
+    pub fn complete_pomodoro(&mut self) {
+        self.pomodoro_intervals += 1;
+        self.revision += 1;
+    }
+
+    /// Histogram of gaps between consecutive keystrokes, keyed by bucket start
+    /// in ms. Gaps of `ACTIVE_GAP_THRESHOLD_MS` or more are counted in a single
+    /// idle bucket keyed by the threshold itself. Requires `track_keystroke_timing`.
+    pub fn keystroke_interval_histogram(&self, bucket_ms: u64) -> HashMap<u64, u32> {
+        let mut histogram = HashMap::new();
+        if bucket_ms == 0 {
+            return histogram;
+        }
+
+        for pair in self.keystroke_timestamps.windows(2) {
+            let gap = (pair[1] - pair[0]).num_milliseconds().max(0) as u64;
+            let bucket = if gap >= ACTIVE_GAP_THRESHOLD_MS {
+                ACTIVE_GAP_THRESHOLD_MS
+            } else {
+                gap / bucket_ms * bucket_ms
+            };
+            *histogram.entry(bucket).or_insert(0) += 1;
+        }
+
+        histogram
+    }
+
+    /// Total time spent in recorded idle periods
+    pub fn idle_time_ms(&self) -> u64 {
+        self.idle_periods.iter().map(|p| p.duration_ms).sum()
+    }
+
+    /// Total time spent in recorded pause periods
+    pub fn paused_time_ms(&self) -> u64 {
+        self.pause_periods.iter().map(|p| p.duration_ms).sum()
     }
 
     /// Get the total duration of the session in milliseconds
     pub fn total_duration_ms(&self) -> u64 {
         let end = self.ended_at.unwrap_or_else(Utc::now);
-        (end - self.started_at).num_milliseconds() as u64
+        (end - self.started_at).num_milliseconds().max(0) as u64
     }
 
     /// Calculate active percentage (0.0 - 1.0)
@@ -185,23 +621,88 @@ impl Session {
         self.active_time_ms as f32 / total as f32
     }
 
+    /// Keystrokes per minute of active time
+    pub fn keystroke_density(&self) -> f32 {
+        if self.active_time_ms == 0 {
+            return 0.0;
+        }
+        self.keystroke_count as f32 / (self.active_time_ms as f32 / 60_000.0)
+    }
+
+    /// Idle interruptions per hour of active time
+    pub fn interruptions_per_hour(&self) -> f32 {
+        if self.active_time_ms == 0 {
+            return 0.0;
+        }
+        self.idle_periods.len() as f32 / (self.active_time_ms as f32 / 3_600_000.0)
+    }
+
+    /// Focus score (0-100): active percentage, reduced 10% per idle interruption
+    pub fn focus_score(&self) -> f32 {
+        self.active_percentage() * 100.0 * 0.9f32.powi(self.idle_periods.len() as i32)
+    }
+
+    /// Snapshot of the fields a live view needs on each tick
+    pub fn delta(&self) -> SessionDelta {
+        SessionDelta {
+            revision: self.revision,
+            keystroke_count: self.keystroke_count,
+            active_time_ms: self.active_time_ms,
+            state: self.state,
+            primary_language: self.primary_language(),
+        }
+    }
+
     /// Get the primary language (most time spent)
     pub fn primary_language(&self) -> Option<String> {
+        if !self.primary_language_cached() {
+            return self.scan_primary_language();
+        }
+        self.cached_primary_language.clone()
+    }
+
+    /// Whether the cached primary language was built from the current `languages`
+    fn primary_language_cached(&self) -> bool {
+        languages_fingerprint(&self.languages) == self.cached_languages_fingerprint
+    }
+
+    fn scan_primary_language(&self) -> Option<String> {
         self.languages
             .iter()
             .max_by_key(|(_, time)| *time)
             .map(|(lang, _)| lang.clone())
-        
     }
 
-    fn update_activity_time(&mut self) {
+    /// Drop keystrokes older than `ENGAGED_WINDOW_MS`, keeping at most the
+    /// `ENGAGED_MIN_KEYSTROKES` newest: that is all the engaged check needs
+    fn trim_recent_keystrokes(&mut self, now: DateTime<Utc>) {
+        let window_start = now - chrono::Duration::milliseconds(ENGAGED_WINDOW_MS);
+        while self.recent_keystrokes.len() > ENGAGED_MIN_KEYSTROKES
+            || self.recent_keystrokes.front().is_some_and(|t| *t < window_start)
+        {
+            self.recent_keystrokes.pop_front();
+        }
+    }
+
+    fn update_activity_time(&mut self, kind: ActivityKind) {
+        self.revision += 1;
+
         if self.state == SessionState::Active {
             let now = Utc::now();
-            let delta = (now - self.last_activity).num_milliseconds() as u64;
+            let delta = (now - self.last_activity).num_milliseconds().max(0) as u64;
             
-            // Only count if activity is within reasonable bounds (< 5 seconds gap)
-            if delta < 5000 {
+            // Only count if activity is within reasonable bounds (< 5 seconds gap);
+            // very long gaps are recorded as an idle period the editor never reported
+            if delta < ACTIVE_GAP_THRESHOLD_MS {
                 self.active_time_ms += delta;
+                *self.activity_times.entry(kind).or_insert(0) += delta;
+
+                self.trim_recent_keystrokes(now);
+                if self.recent_keystrokes.len() >= ENGAGED_MIN_KEYSTROKES {
+                    self.engaged_time_ms += delta;
+                }
+            } else {
+                self.record_auto_idle(now);
             }
             
             self.last_activity = now;
@@ -226,6 +727,135 @@ mod tests {
         let mut session = Session::new(1);
         session.record_keystroke();
         assert_eq!(session.keystroke_count, 1);
+        assert_eq!(session.raw_keystroke_count, 1);
+    }
+
+    #[test]
+    fn test_keystroke_burst_is_clamped() {
+        let mut session = Session::new(1);
+        session.burst_window_ms = 60_000;
+        for _ in 0..1000 {
+            session.record_keystroke();
+        }
+        assert_eq!(session.keystroke_count, session.burst_limit);
+        assert_eq!(session.raw_keystroke_count, 1000);
+    }
+
+    #[test]
+    fn test_keystroke_interval_histogram() {
+        let mut session = Session::new(1);
+        session.track_keystroke_timing = true;
+        session.record_keystroke();
+        assert_eq!(session.keystroke_timestamps.len(), 1);
+
+        let base = session.started_at;
+        session.keystroke_timestamps = [0, 120, 250, 330, 7000, 7050]
+            .iter()
+            .map(|ms| base + chrono::Duration::milliseconds(*ms))
+            .collect();
+
+        let histogram = session.keystroke_interval_histogram(100);
+        assert_eq!(histogram.get(&0), Some(&2));
+        assert_eq!(histogram.get(&100), Some(&2));
+        assert_eq!(histogram.get(&ACTIVE_GAP_THRESHOLD_MS), Some(&1));
+        assert_eq!(histogram.values().sum::<u32>(), 5);
+    }
+
+    #[test]
+    fn test_keystroke_timestamps_capped() {
+        let mut session = Session::new(1);
+        session.track_keystroke_timing = true;
+        for _ in 0..MAX_KEYSTROKE_TIMESTAMPS + 50 {
+            session.record_keystroke();
+        }
+        assert_eq!(session.keystroke_timestamps.len(), MAX_KEYSTROKE_TIMESTAMPS);
+        assert_eq!(session.raw_keystroke_count, MAX_KEYSTROKE_TIMESTAMPS as u32 + 50);
+    }
+
+    #[test]
+    fn test_normalize_path() {
+        assert_eq!(normalize_path("./src/main.rs"), "src/main.rs");
+        assert_eq!(normalize_path("src\\lib\\mod.rs"), "src/lib/mod.rs");
+        assert_eq!(normalize_path("src/util/../main.rs"), "src/main.rs");
+        assert_eq!(normalize_path("../shared//lib.rs"), "../shared/lib.rs");
+        assert_eq!(normalize_path("/home/me/./a.rs"), "/home/me/a.rs");
+    }
+
+    #[test]
+    fn test_record_file_edit_dedupes_normalized_paths() {
+        let mut session = Session::new(1);
+        session.record_file_edit("src/main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("./src/main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("src\\main.rs".to_string(), "rust".to_string());
+        assert_eq!(session.files_edited, vec!["src/main.rs".to_string()]);
+
+        let mut raw = Session::new(2);
+        raw.normalize_paths = false;
+        raw.record_file_edit("src/main.rs".to_string(), "rust".to_string());
+        raw.record_file_edit("./src/main.rs".to_string(), "rust".to_string());
+        assert_eq!(raw.files_edited.len(), 2);
+    }
+
+    #[test]
+    fn test_record_file_edits_batch() {
+        let mut session = Session::new(1);
+        let revision = session.revision;
+        let edit = |path: &str| FileEdit {
+            path: path.to_string(),
+            language: "rust".to_string(),
+        };
+
+        session.record_file_edits(vec![edit("src/a.rs"), edit("src/b.rs"), edit("./src/c.rs")]);
+
+        assert_eq!(session.files_edited, vec!["src/a.rs", "src/b.rs", "src/c.rs"]);
+        assert_eq!(session.file_languages.len(), 3);
+        assert_eq!(session.languages.get("rust"), Some(&3000));
+        assert_eq!(session.revision, revision + 1);
+
+        session.record_file_edits(vec![edit("src/a.rs")]);
+        assert_eq!(session.files_edited.len(), 3);
+    }
+
+    #[test]
+    fn test_activity_kind_breakdown() {
+        let mut session = Session::new(1);
+
+        session.last_activity = Utc::now() - chrono::Duration::milliseconds(1000);
+        session.record_keystroke();
+        session.last_activity = Utc::now() - chrono::Duration::milliseconds(2000);
+        session.record_activity(ActivityKind::TestRun, None, None);
+        session.last_activity = Utc::now() - chrono::Duration::milliseconds(1000);
+        session.record_activity(
+            ActivityKind::TestRun,
+            Some("tests/api.rs".to_string()),
+            Some("rust".to_string()),
+        );
+
+        let edit = session.activity_times[&ActivityKind::Edit];
+        let test_run = session.activity_times[&ActivityKind::TestRun];
+        assert!((1000..2000).contains(&edit));
+        assert!((3000..4000).contains(&test_run));
+        assert!(!session.activity_times.contains_key(&ActivityKind::Build));
+        let stats = SessionStats::from_session(&session);
+        assert_eq!(stats.activity_breakdown[&ActivityKind::TestRun], test_run);
+        assert_eq!(session.files_edited, vec!["tests/api.rs".to_string()]);
+        assert_eq!(ActivityKind::parse("test_run"), Some(ActivityKind::TestRun));
+        assert_eq!(ActivityKind::parse("lint"), None);
+    }
+
+    #[test]
+    fn test_revision_tracks_changes() {
+        let mut session = Session::new(1);
+        let start = session.delta().revision;
+
+        session.record_keystroke();
+        let after_keystroke = session.delta();
+        assert_eq!(after_keystroke.revision, start + 1);
+        assert_eq!(after_keystroke.keystroke_count, 1);
+
+        let _ = session.total_duration_ms();
+        let _ = session.keystroke_interval_histogram(100);
+        assert_eq!(session.delta().revision, after_keystroke.revision);
     }
 
     #[test]
@@ -239,6 +869,237 @@ mod tests {
         assert_eq!(session.idle_periods.len(), 1);
     }
 
+    #[test]
+    fn test_pause_flow_records_pause_period() {
+        let mut session = Session::new(1);
+        session.pause();
+        session.pause();
+        assert_eq!(session.state, SessionState::Paused);
+
+        session.resume();
+        assert_eq!(session.state, SessionState::Active);
+        assert_eq!(session.pause_periods.len(), 1);
+        assert!(session.pause_periods[0].ended_at.is_some());
+        assert!(session.idle_periods.is_empty());
+
+        session.pause();
+        session.end();
+        assert_eq!(session.pause_periods.len(), 2);
+    }
+
+    #[test]
+    fn test_interruptions_per_hour() {
+        let mut session = Session::new(1);
+        assert_eq!(session.interruptions_per_hour(), 0.0);
+
+        session.active_time_ms = 2 * 60 * 60 * 1000;
+        for _ in 0..3 {
+            session.idle_periods.push(IdlePeriod::new(Utc::now()));
+        }
+        assert!((session.interruptions_per_hour() - 1.5).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_engaged_time_requires_sustained_typing() {
+        let mut session = Session::new(1);
+
+        // Reading: an occasional edit keeps the session active but not engaged
+        session.last_activity = Utc::now() - chrono::Duration::milliseconds(2000);
+        session.record_file_edit("src/lib.rs".to_string(), "rust".to_string());
+        assert!(session.active_time_ms >= 2000);
+        assert_eq!(session.engaged_time_ms, 0);
+
+        // Typing: enough recent keystrokes make the next interval engaged
+        for _ in 0..ENGAGED_MIN_KEYSTROKES {
+            session.record_keystroke();
+        }
+        let engaged_before = session.engaged_time_ms;
+        session.last_activity = Utc::now() - chrono::Duration::milliseconds(2000);
+        session.record_keystroke();
+        assert!(session.engaged_time_ms - engaged_before >= 2000);
+        assert!(session.engaged_time_ms <= session.active_time_ms);
+        assert_eq!(
+            SessionStats::from_session(&session).engaged_time_ms,
+            session.engaged_time_ms
+        );
+    }
+
+    #[test]
+    fn test_recent_keystrokes_bounded_while_paused() {
+        let mut session = Session::new(1);
+        session.burst_limit = u32::MAX;
+        session.pause();
+        for _ in 0..500 {
+            session.record_keystroke();
+        }
+        assert_eq!(session.keystroke_count, 500);
+        assert_eq!(session.recent_keystrokes.len(), ENGAGED_MIN_KEYSTROKES);
+    }
+
+    #[test]
+    fn test_backward_clock_clamps_durations() {
+        let future = Utc::now() + chrono::Duration::hours(1);
+
+        let mut idle = IdlePeriod::new(future);
+        idle.end(Utc::now());
+        assert_eq!(idle.duration_ms, 0);
+
+        let mut session = Session::new_at(1, future);
+        assert_eq!(session.total_duration_ms(), 0);
+
+        session.last_activity = future;
+        session.record_keystroke();
+        assert_eq!(session.active_time_ms, 0);
+        assert!(session.idle_periods.is_empty());
+
+        // A burst window started in the future is reset instead of throttling
+        session.burst_window_start = future;
+        session.burst_window_count = session.burst_limit;
+        let counted = session.keystroke_count;
+        session.record_keystroke();
+        assert_eq!(session.keystroke_count, counted + 1);
+        assert_eq!(session.burst_window_count, 1);
+        assert!(session.burst_window_start < future);
+    }
+
+    #[test]
+    fn test_complete_pomodoro() {
+        let mut session = Session::new(1);
+        session.complete_pomodoro();
+        session.complete_pomodoro();
+        assert_eq!(session.pomodoro_intervals, 2);
+    }
+
+    #[test]
+    fn test_coalesce_idle_periods() {
+        let mut session = Session::new(1);
+        let base = session.started_at;
+        let at = |ms: i64| base + chrono::Duration::milliseconds(ms);
+        for (start, end) in [(0, 300), (500, 900), (1000, 1200), (60_000, 61_000)] {
+            let mut idle = IdlePeriod::new(at(start));
+            idle.end(at(end));
+            session.idle_periods.push(idle);
+        }
+
+        session.coalesce_idle_periods(1000);
+
+        assert_eq!(session.idle_periods.len(), 2);
+        assert_eq!(session.idle_periods[0].started_at, at(0));
+        assert_eq!(session.idle_periods[0].ended_at, Some(at(1200)));
+        assert_eq!(session.idle_periods[0].duration_ms, 900);
+        assert_eq!(session.idle_periods[1].duration_ms, 1000);
+    }
+
+    #[test]
+    fn test_coalesce_overlapping_idle_periods() {
+        let mut session = Session::new(1);
+        let base = session.started_at;
+        let at = |ms: i64| base + chrono::Duration::milliseconds(ms);
+        for (start, end) in [(0, 1000), (500, 1500), (600, 800), (2000, 2500)] {
+            let mut idle = IdlePeriod::new(at(start));
+            idle.end(at(end));
+            session.idle_periods.push(idle);
+        }
+
+        session.coalesce_idle_periods(100);
+
+        assert_eq!(session.idle_periods.len(), 2);
+        assert_eq!(session.idle_periods[0].ended_at, Some(at(1500)));
+        assert_eq!(session.idle_periods[0].duration_ms, 1500);
+        assert_eq!(session.idle_periods[1].duration_ms, 500);
+        assert_eq!(session.idle_time_ms(), 2000);
+    }
+
+    #[test]
+    fn test_long_gap_records_idle_period() {
+        let mut session = Session::new(1);
+        session.record_keystroke();
+        assert!(session.idle_periods.is_empty());
+
+        // A reading pause is neither active nor an interruption
+        session.last_activity = Utc::now() - chrono::Duration::seconds(60);
+        session.record_keystroke();
+        assert!(session.idle_periods.is_empty());
+        assert!(session.active_time_ms < ACTIVE_GAP_THRESHOLD_MS);
+
+        // Only the excess beyond the auto-idle threshold becomes idle time
+        session.last_activity = Utc::now() - chrono::Duration::minutes(10);
+        session.record_keystroke();
+
+        assert_eq!(session.idle_periods.len(), 1);
+        let idle = &session.idle_periods[0];
+        assert!(idle.ended_at.is_some());
+        let excess = 10 * 60_000 - AUTO_IDLE_THRESHOLD_MS;
+        assert!(idle.duration_ms >= excess && idle.duration_ms < excess + 1000);
+        assert!(session.active_time_ms < ACTIVE_GAP_THRESHOLD_MS);
+    }
+
+    #[test]
+    fn test_spurious_resume_records_gap() {
+        let mut session = Session::new(1);
+        session.record_keystroke();
+
+        // Resume without mark_idle after a long silence keeps the gap as idle
+        session.last_activity = Utc::now() - chrono::Duration::minutes(5);
+        session.resume_from_idle();
+        assert_eq!(session.state, SessionState::Active);
+        assert_eq!(session.idle_periods.len(), 1);
+
+        // ...and the next keystroke doesn't count the gap a second time
+        session.record_keystroke();
+        assert_eq!(session.idle_periods.len(), 1);
+
+        // A spurious resume right after activity records nothing
+        session.resume_from_idle();
+        assert_eq!(session.idle_periods.len(), 1);
+    }
+
+    #[test]
+    fn test_primary_language_cache_follows_new_leader() {
+        let mut session = Session::new(1);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        assert_eq!(session.primary_language(), Some("rust".to_string()));
+
+        for _ in 0..3 {
+            session.record_file_edit("app.py".to_string(), "python".to_string());
+        }
+        assert_eq!(session.primary_language(), Some("python".to_string()));
+        assert_eq!(session.primary_language(), session.scan_primary_language());
+
+        // Direct edits to the public map are picked up without any flagging
+        session.languages.insert("go".to_string(), 60_000);
+        assert!(!session.primary_language_cached());
+        assert_eq!(session.primary_language(), Some("go".to_string()));
+        session.languages.clear();
+        assert_eq!(session.primary_language(), None);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        assert_eq!(session.primary_language(), Some("rust".to_string()));
+
+        // Rehydrating rebuilds the cache from the edited map
+        session.languages.insert("go".to_string(), 60_000);
+        session.rehydrate();
+        assert!(session.primary_language_cached());
+        assert_eq!(session.primary_language(), Some("go".to_string()));
+    }
+
+    #[test]
+    fn test_primary_language_poll_uses_cache() {
+        let mut session = Session::new(1);
+        for i in 0..200 {
+            session.record_file_edit(format!("src/file_{}.x", i), format!("lang{}", i));
+        }
+        session.record_file_edit("src/file_42.x".to_string(), "lang42".to_string());
+        assert!(session.primary_language_cached());
+
+        for _ in 0..100 {
+            let stats = SessionStats::from_session(&session);
+            assert_eq!(stats.primary_language, Some("lang42".to_string()));
+        }
+        assert!(session.primary_language_cached());
+        assert_eq!(session.cached_primary_language, session.scan_primary_language());
+    }
+
     #[test]
     fn test_session_end() {
         let mut session = Session::new(1);
@@ -246,4 +1107,21 @@ mod tests {
         assert_eq!(session.state, SessionState::Ended);
         assert!(session.ended_at.is_some());
     }
+
+    #[test]
+    fn test_end_freezes_stats() {
+        let mut session = Session::new(1);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        assert!(session.final_stats.is_none());
+
+        session.end();
+        let frozen = session.final_stats.clone().unwrap();
+        assert_eq!(frozen.primary_language, Some("rust".to_string()));
+
+        let json = serde_json::to_string(&session).unwrap();
+        let restored: Session = serde_json::from_str(&json).unwrap();
+        let restored_stats = restored.final_stats.unwrap();
+        assert_eq!(restored_stats.total_duration_ms, frozen.total_duration_ms);
+        assert_eq!(restored_stats.primary_language, frozen.primary_language);
+    }
 }
@@ -1,9 +1,23 @@
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, Datelike, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use uuid::Uuid;
 
-use crate::session::Session;
+use crate::session::{ActivityKind, CommitRef, Session};
+
+/// Serialize a `HashMap` with its keys in sorted order, so identical data
+/// always produces identical JSON
+pub(crate) fn serialize_sorted<S, K, V>(
+    map: &HashMap<K, V>,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    K: Ord + Serialize,
+    V: Serialize,
+{
+    map.iter().collect::<BTreeMap<_, _>>().serialize(serializer)
+}
 
 /// Computed statistics for a session
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +26,14 @@ pub struct SessionStats {
     pub active_percentage: f32,
     pub primary_language: Option<String>,
     pub commit_count: u32,
+    #[serde(default)]
+    pub pomodoro_intervals: u32,
+    #[serde(default, serialize_with = "serialize_sorted")]
+    pub activity_breakdown: HashMap<ActivityKind, u64>,
+    #[serde(default)]
+    pub interruptions_per_hour: f32,
+    #[serde(default)]
+    pub engaged_time_ms: u64,
 }
 
 impl SessionStats {
@@ -21,8 +43,53 @@ impl SessionStats {
             active_percentage: session.active_percentage(),
             primary_language: session.primary_language(),
             commit_count: session.commits.len() as u32,
+            pomodoro_intervals: session.pomodoro_intervals,
+            activity_breakdown: session.activity_times.clone(),
+            interruptions_per_hour: session.interruptions_per_hour(),
+            engaged_time_ms: session.engaged_time_ms,
+        }
+    }
+}
+
+/// How a session compares to the profile's historical averages,
+/// as percent differences (positive means above average)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionComparison {
+    pub keystroke_density_diff: f32,
+    pub active_percentage_diff: f32,
+    pub focus_score_diff: f32,
+}
+
+impl SessionComparison {
+    pub fn compare(session: &Session, profile: &GrowthProfile) -> Self {
+        let count = profile.counted_sessions().count() as f32;
+        if count == 0.0 {
+            return Self::default();
         }
+
+        let average = |metric: fn(&Session) -> f32| {
+            profile.counted_sessions().map(|s| metric(&s.session)).sum::<f32>() / count
+        };
+
+        Self {
+            keystroke_density_diff: percent_diff(
+                session.keystroke_density(),
+                average(Session::keystroke_density),
+            ),
+            active_percentage_diff: percent_diff(
+                session.active_percentage(),
+                average(Session::active_percentage),
+            ),
+            focus_score_diff: percent_diff(session.focus_score(), average(Session::focus_score)),
+        }
+    }
+}
+
+fn percent_diff(value: f32, baseline: f32) -> f32 {
+    if baseline == 0.0 {
+        return 0.0;
     }
+    (value - baseline) / baseline * 100.0
 }
 
 /// Persisted session data with computed statistics
@@ -34,7 +101,10 @@ pub struct StoredSession {
 
 impl StoredSession {
     pub fn new(session: Session) -> Self {
-        let computed_stats = SessionStats::from_session(&session);
+        let computed_stats = session
+            .final_stats
+            .clone()
+            .unwrap_or_else(|| SessionStats::from_session(&session));
         Self {
             session,
             computed_stats,
@@ -51,7 +121,12 @@ pub struct DailyAggregate {
     pub files_count: u32,
     pub sessions_count: u32,
     pub commits_count: u32,
+    #[serde(serialize_with = "serialize_sorted")]
     pub languages: HashMap<String, u64>,
+    /// Active time before the profile's daily cap; `total_time_ms` is this
+    /// clamped to the cap. Older profiles without it fall back to the total.
+    #[serde(default)]
+    pub uncapped_time_ms: u64,
 }
 
 impl DailyAggregate {
@@ -64,11 +139,24 @@ impl DailyAggregate {
             sessions_count: 0,
             commits_count: 0,
             languages: HashMap::new(),
+            uncapped_time_ms: 0,
         }
     }
 
+    /// Active time before any daily cap was applied
+    pub fn uncapped_time(&self) -> u64 {
+        self.uncapped_time_ms.max(self.total_time_ms)
+    }
+
+    /// Recompute `total_time_ms` from the uncapped time under `cap`
+    fn apply_cap(&mut self, cap: Option<u64>) {
+        self.uncapped_time_ms = self.uncapped_time();
+        self.total_time_ms = cap.map_or(self.uncapped_time_ms, |c| self.uncapped_time_ms.min(c));
+    }
+
     pub fn add_session(&mut self, session: &Session) {
         self.sessions_count += 1;
+        self.uncapped_time_ms = self.uncapped_time() + session.active_time_ms;
         self.total_time_ms += session.active_time_ms;
         self.total_keystrokes += session.keystroke_count;
         self.commits_count += session.commits.len() as u32;
@@ -82,10 +170,56 @@ impl DailyAggregate {
             *self.languages.entry(lang.clone()).or_insert(0) += time;
         }
     }
+
+    /// Fold another aggregate for the same date into this one
+    fn merge(&mut self, other: DailyAggregate) {
+        self.uncapped_time_ms = self.uncapped_time() + other.uncapped_time();
+        self.total_time_ms += other.total_time_ms;
+        self.total_keystrokes += other.total_keystrokes;
+        self.files_count += other.files_count;
+        self.sessions_count += other.sessions_count;
+        self.commits_count += other.commits_count;
+        for (lang, time) in other.languages {
+            *self.languages.entry(lang).or_insert(0) += time;
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.total_time_ms == 0
+            && self.total_keystrokes == 0
+            && self.files_count == 0
+            && self.sessions_count == 0
+            && self.commits_count == 0
+    }
+
+    /// Undo a previous `add_session` for the same session
+    pub fn remove_session(&mut self, session: &Session) {
+        self.sessions_count = self.sessions_count.saturating_sub(1);
+        self.uncapped_time_ms = self.uncapped_time().saturating_sub(session.active_time_ms);
+        self.total_time_ms = self.total_time_ms.saturating_sub(session.active_time_ms);
+        self.total_keystrokes = self.total_keystrokes.saturating_sub(session.keystroke_count);
+        self.commits_count = self.commits_count.saturating_sub(session.commits.len() as u32);
+
+        let unique_files: std::collections::HashSet<_> = session.files_edited.iter().collect();
+        self.files_count = self.files_count.saturating_sub(unique_files.len() as u32);
+
+        subtract_languages(&mut self.languages, &session.languages);
+    }
+}
+
+fn subtract_languages(totals: &mut HashMap<String, u64>, removed: &HashMap<String, u64>) {
+    for (lang, time) in removed {
+        if let Some(total) = totals.get_mut(lang) {
+            *total = total.saturating_sub(*time);
+            if *total == 0 {
+                totals.remove(lang);
+            }
+        }
+    }
 }
 
 /// All-time statistics for a user
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LifetimeStats {
     pub total_time_ms: u64,
     pub total_keystrokes: u64,
@@ -93,21 +227,10 @@ pub struct LifetimeStats {
     pub total_commits: u32,
     pub current_streak: u32,
     pub longest_streak: u32,
+    #[serde(serialize_with = "serialize_sorted")]
     pub languages: HashMap<String, u64>,
-}
-
-impl Default for LifetimeStats {
-    fn default() -> Self {
-        Self {
-            total_time_ms: 0,
-            total_keystrokes: 0,
-            total_sessions: 0,
-            total_commits: 0,
-            current_streak: 0,
-            longest_streak: 0,
-            languages: HashMap::new(),
-        }
-    }
+    #[serde(default)]
+    pub total_pomodoros: u32,
 }
 
 impl LifetimeStats {
@@ -116,6 +239,7 @@ impl LifetimeStats {
         self.total_keystrokes += session.keystroke_count as u64;
         self.total_sessions += 1;
         self.total_commits += session.commits.len() as u32;
+        self.total_pomodoros += session.pomodoro_intervals;
 
         // Merge languages
         for (lang, time) in &session.languages {
@@ -123,99 +247,705 @@ impl LifetimeStats {
         }
     }
 
-    pub fn recalculate_streaks(&mut self, daily_aggregates: &[DailyAggregate]) {
-        if daily_aggregates.is_empty() {
-            self.current_streak = 0;
-            self.longest_streak = 0;
-            return;
+    /// Undo a previous `update_from_session` for the same session
+    pub fn remove_session(&mut self, session: &Session) {
+        self.total_time_ms = self.total_time_ms.saturating_sub(session.active_time_ms);
+        self.total_keystrokes = self
+            .total_keystrokes
+            .saturating_sub(session.keystroke_count as u64);
+        self.total_sessions = self.total_sessions.saturating_sub(1);
+        self.total_commits = self.total_commits.saturating_sub(session.commits.len() as u32);
+        self.total_pomodoros = self.total_pomodoros.saturating_sub(session.pomodoro_intervals);
+
+        subtract_languages(&mut self.languages, &session.languages);
+    }
+
+    /// Recalculate streaks up to `today`, in the same local time the aggregates
+    /// are bucketed in (see `GrowthProfile::today`)
+    pub fn recalculate_streaks(&mut self, daily_aggregates: &[DailyAggregate], today: NaiveDate) {
+        self.recalculate_streaks_with_frozen(daily_aggregates, &[], today);
+    }
+
+    /// Recalculate streaks, treating `frozen_dates` as if they were active.
+    /// `today` should be in the same local time the aggregates are bucketed in.
+    pub fn recalculate_streaks_with_frozen(
+        &mut self,
+        daily_aggregates: &[DailyAggregate],
+        frozen_dates: &[NaiveDate],
+        today: NaiveDate,
+    ) {
+        let mut dates: Vec<_> = daily_aggregates.iter().map(|d| d.date).collect();
+        dates.extend_from_slice(frozen_dates);
+        self.set_streaks_from_dates(dates, today);
+    }
+
+    fn set_streaks_from_dates(&mut self, dates: Vec<NaiveDate>, today: NaiveDate) {
+        let (current_streak, longest_streak) = compute_streaks(dates, today);
+
+        self.current_streak = current_streak;
+        self.longest_streak = longest_streak;
+    }
+}
+
+/// Calendar date of a timestamp shifted by a UTC offset in minutes
+pub(crate) fn local_date(timestamp: DateTime<Utc>, utc_offset_minutes: i32) -> NaiveDate {
+    (timestamp + chrono::Duration::minutes(utc_offset_minutes as i64)).date_naive()
+}
+
+/// Compute (current, longest) streaks of consecutive days.
+/// The current streak must include today or yesterday.
+fn compute_streaks(dates: Vec<NaiveDate>, today: NaiveDate) -> (u32, u32) {
+    if dates.is_empty() {
+        return (0, 0);
+    }
+
+    let mut sorted_dates = dates;
+    sorted_dates.sort();
+    sorted_dates.dedup();
+
+    let current_streak = current_run(&sorted_dates, today).len() as u32;
+    let mut longest_streak = 0;
+    let mut temp_streak = 1;
+
+    // Calculate longest streak
+    for i in 1..sorted_dates.len() {
+        let diff = (sorted_dates[i] - sorted_dates[i - 1]).num_days();
+        if diff == 1 {
+            temp_streak += 1;
+            longest_streak = longest_streak.max(temp_streak);
+        } else {
+            temp_streak = 1;
         }
+    }
+    longest_streak = longest_streak.max(temp_streak);
 
-        let mut sorted_dates: Vec<_> = daily_aggregates.iter().map(|d| d.date).collect();
-        sorted_dates.sort();
+    (current_streak, longest_streak)
+}
 
-        let today = Utc::now().date_naive();
-        let mut current_streak = 0;
-        let mut longest_streak = 0;
-        let mut temp_streak = 1;
-
-        // Calculate current streak (must include today or yesterday)
-        if let Some(&last_date) = sorted_dates.last() {
-            let days_since = (today - last_date).num_days();
-            if days_since <= 1 {
-                current_streak = 1;
-                for i in (0..sorted_dates.len() - 1).rev() {
-                    let diff = (sorted_dates[i + 1] - sorted_dates[i]).num_days();
-                    if diff == 1 {
-                        current_streak += 1;
-                    } else {
-                        break;
-                    }
-                }
-            }
+/// The trailing run of consecutive dates, if it reaches today or yesterday.
+/// `sorted_dates` must be sorted and free of duplicates.
+fn current_run(sorted_dates: &[NaiveDate], today: NaiveDate) -> &[NaiveDate] {
+    let last_date = match sorted_dates.last() {
+        Some(&d) if (today - d).num_days() <= 1 => d,
+        _ => return &[],
+    };
+
+    let run_length = sorted_dates
+        .iter()
+        .rev()
+        .enumerate()
+        .take_while(|(i, date)| (last_date - **date).num_days() == *i as i64)
+        .count();
+
+    &sorted_dates[sorted_dates.len() - run_length..]
+}
+
+/// Dates making up the current calendar streak, including frozen days
+pub fn current_streak_dates(profile: &GrowthProfile) -> Vec<NaiveDate> {
+    let mut dates = profile.active_dates();
+    dates.extend_from_slice(&profile.frozen_dates);
+    dates.sort();
+    dates.dedup();
+
+    current_run(&dates, profile.today()).to_vec()
+}
+
+/// How a streak that lapsed yesterday could be restored by coding today
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreakRecovery {
+    /// The missed day that would need to be bridged by a freeze token
+    pub missed_date: NaiveDate,
+    /// Length of the run that ended the day before `missed_date`
+    pub broken_streak: u32,
+    /// Streak length after coding today with `missed_date` bridged
+    pub restored_streak: u32,
+    pub freeze_tokens_available: u32,
+}
+
+/// When the only gap in the most recent run is yesterday, report what coding
+/// today would restore. None when the streak is still alive, the lapse is
+/// longer than one day, or no freeze token can bridge the missed day.
+pub fn streak_recovery_hint(profile: &GrowthProfile) -> Option<StreakRecovery> {
+    streak_recovery_hint_on(profile, profile.today())
+}
+
+fn streak_recovery_hint_on(profile: &GrowthProfile, today: NaiveDate) -> Option<StreakRecovery> {
+    let mut dates = profile.active_dates();
+    dates.extend_from_slice(&profile.frozen_dates);
+    dates.sort();
+    dates.dedup();
+
+    let yesterday = today.pred_opt()?;
+    let last = *dates.last()?;
+    if (today - last).num_days() != 2 {
+        return None;
+    }
+    let bridgeable = profile.freeze_tokens > 0
+        && profile.freeze_tokens_granted_after.is_none_or(|d| yesterday > d);
+    if !bridgeable {
+        return None;
+    }
+
+    let broken_streak = current_run(&dates, yesterday).len() as u32;
+    Some(StreakRecovery {
+        missed_date: yesterday,
+        broken_streak,
+        restored_streak: broken_streak + 2,
+        freeze_tokens_available: profile.freeze_tokens,
+    })
+}
+
+/// Longest run of inactive days between two active days, as (first inactive
+/// day, last inactive day, length in days). Returns None with fewer than two
+/// active days or when active days have no gaps between them. Ties resolve to
+/// the earliest gap.
+pub fn longest_inactivity_gap(profile: &GrowthProfile) -> Option<(NaiveDate, NaiveDate, u32)> {
+    let dates = profile.active_dates();
+
+    let mut longest: Option<(NaiveDate, NaiveDate, u32)> = None;
+    for pair in dates.windows(2) {
+        let gap_days = (pair[1] - pair[0]).num_days() - 1;
+        if gap_days > 0 && longest.is_none_or(|(_, _, days)| gap_days as u32 > days) {
+            longest = Some((
+                pair[0] + chrono::Duration::days(1),
+                pair[1] - chrono::Duration::days(1),
+                gap_days as u32,
+            ));
         }
+    }
 
-        // Calculate longest streak
-        for i in 1..sorted_dates.len() {
-            let diff = (sorted_dates[i] - sorted_dates[i - 1]).num_days();
-            if diff == 1 {
-                temp_streak += 1;
-                longest_streak = longest_streak.max(temp_streak);
-            } else {
-                temp_streak = 1;
-            }
+    longest
+}
+
+/// Typing speed above which a session's keystroke count is implausible
+pub const MAX_PLAUSIBLE_WPM: f32 = 1000.0;
+
+/// Sessions with implausible metrics, as (session id, reason). A session may
+/// appear once per problem found.
+pub fn flag_anomalous_sessions(profile: &GrowthProfile) -> Vec<(u64, String)> {
+    let mut flagged = Vec::new();
+
+    for stored_session in &profile.sessions {
+        let session = &stored_session.session;
+
+        if session.ended_at.is_some_and(|ended_at| ended_at < session.started_at) {
+            flagged.push((session.id, "ended before it started".to_string()));
+        } else if session.active_time_ms > session.total_duration_ms() {
+            flagged.push((session.id, "active time exceeds total duration".to_string()));
         }
-        longest_streak = longest_streak.max(temp_streak);
 
-        self.current_streak = current_streak;
-        self.longest_streak = longest_streak;
+        // Standard five characters per word
+        let wpm = session.keystroke_density() / 5.0;
+        if session.keystroke_count > 0 && session.active_time_ms == 0 {
+            flagged.push((
+                session.id,
+                format!("{} keystrokes with no active time", session.keystroke_count),
+            ));
+        } else if wpm > MAX_PLAUSIBLE_WPM {
+            flagged.push((session.id, format!("typing speed of {:.0} WPM", wpm)));
+        }
     }
+
+    flagged
 }
 
+/// Compute (current, longest) streaks counting only days that met the daily goal
+pub fn goal_streak(profile: &GrowthProfile, daily_goal_ms: u64) -> (u32, u32) {
+    let dates = profile
+        .daily_aggregates
+        .iter()
+        .filter(|d| d.total_time_ms >= daily_goal_ms)
+        .map(|d| d.date)
+        .collect();
+
+    compute_streaks(dates, profile.today())
+}
+
+/// End-of-year summary scoped to one calendar year
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct YearReview {
+    pub year: i32,
+    pub total_time_ms: u64,
+    pub total_hours: f32,
+    pub active_days: u32,
+    pub longest_streak: u32,
+    /// Up to three languages by time, most used first
+    pub top_languages: Vec<String>,
+    pub total_commits: u32,
+    /// Month (1-12) with the most active time
+    pub best_month: Option<u32>,
+    /// Day with the most active time
+    pub busiest_day: Option<NaiveDate>,
+}
+
+/// Summarize activity within calendar `year`; days outside it are ignored
+pub fn year_in_review(profile: &GrowthProfile, year: i32) -> YearReview {
+    let days: Vec<&DailyAggregate> =
+        profile.daily_aggregates.iter().filter(|d| d.date.year() == year).collect();
+    let stats = match (
+        NaiveDate::from_ymd_opt(year, 1, 1),
+        NaiveDate::from_ymd_opt(year, 12, 31),
+    ) {
+        (Some(start), Some(end)) => profile.stats_for_range(start, end),
+        _ => LifetimeStats::default(),
+    };
+
+    let mut languages: Vec<(&String, &u64)> = stats.languages.iter().collect();
+    languages.sort_by(|a, b| b.1.cmp(a.1).then_with(|| a.0.cmp(b.0)));
+
+    let mut month_times = [0u64; 12];
+    for daily in &days {
+        month_times[daily.date.month0() as usize] += daily.total_time_ms;
+    }
+    let best_month = (0..12)
+        .rev()
+        .max_by_key(|&m| month_times[m])
+        .filter(|&m| month_times[m] > 0)
+        .map(|m| m as u32 + 1);
+
+    let busiest_day = days
+        .iter()
+        .filter(|d| d.total_time_ms > 0)
+        .min_by_key(|d| (std::cmp::Reverse(d.total_time_ms), d.date))
+        .map(|d| d.date);
+
+    let active_days = profile.active_dates().iter().filter(|d| d.year() == year).count();
+
+    YearReview {
+        year,
+        total_time_ms: stats.total_time_ms,
+        total_hours: stats.total_time_ms as f32 / 3_600_000.0,
+        active_days: active_days as u32,
+        longest_streak: stats.longest_streak,
+        top_languages: languages.into_iter().take(3).map(|(l, _)| l.clone()).collect(),
+        total_commits: stats.total_commits,
+        best_month,
+        busiest_day,
+    }
+}
+
+/// How `GrowthProfile::add_session_with_policy` treats a session that was
+/// never ended
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnendedSessionPolicy {
+    /// Stamp `ended_at` with the current time before aggregating
+    #[default]
+    End,
+    /// Refuse to add the session
+    Reject,
+}
+
+/// What `GrowthProfile::compact` cleaned up
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CompactionReport {
+    pub duplicate_aggregates_merged: u32,
+    pub empty_aggregates_removed: u32,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    pub bytes_saved: u64,
+}
+
+/// Progress towards a weekly time goal (weeks start on Monday)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoalPace {
+    pub completed_ms: u64,
+    pub remaining_ms: u64,
+    /// Days left in the week, including today
+    pub days_left: u32,
+    pub suggested_daily_ms: u64,
+}
+
+/// How much time per day is needed to hit a weekly goal from here
+pub fn weekly_goal_pace(profile: &GrowthProfile, weekly_goal_ms: u64) -> GoalPace {
+    weekly_goal_pace_on(profile, weekly_goal_ms, profile.today())
+}
+
+fn weekly_goal_pace_on(profile: &GrowthProfile, weekly_goal_ms: u64, today: NaiveDate) -> GoalPace {
+    let days_into_week = today.weekday().num_days_from_monday();
+    let week_start = today - chrono::Duration::days(days_into_week as i64);
+
+    let completed_ms: u64 = profile
+        .daily_aggregates
+        .iter()
+        .filter(|d| d.date >= week_start && d.date <= today)
+        .map(|d| d.total_time_ms)
+        .sum();
+
+    let remaining_ms = weekly_goal_ms.saturating_sub(completed_ms);
+    let days_left = 7 - days_into_week;
+
+    GoalPace {
+        completed_ms,
+        remaining_ms,
+        days_left,
+        suggested_daily_ms: remaining_ms.div_ceil(days_left as u64),
+    }
+}
+
+/// Schema version written by this build; older profiles are upgraded by `migrate`
+pub const CURRENT_SCHEMA_VERSION: u32 = 1;
+
 /// Complete user learning profile
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GrowthProfile {
+    /// Profiles saved before versioning was introduced deserialize as 0
+    #[serde(default)]
+    pub schema_version: u32,
     pub id: String,
     pub created_at: DateTime<Utc>,
     pub sessions: Vec<StoredSession>,
     pub daily_aggregates: Vec<DailyAggregate>,
     pub lifetime_stats: LifetimeStats,
+    /// Tokens that can be spent to bridge a single missed day in a streak
+    #[serde(default)]
+    pub freeze_tokens: u32,
+    /// Missed days that were bridged by spending a freeze token
+    #[serde(default)]
+    pub frozen_dates: Vec<NaiveDate>,
+    /// Last active day when the outstanding freeze tokens were granted; only
+    /// days missed after it can be bridged
+    #[serde(default)]
+    pub freeze_tokens_granted_after: Option<NaiveDate>,
+    /// Offset applied to session start times when bucketing them into days
+    #[serde(default)]
+    pub utc_offset_minutes: i32,
+    /// Upper bound on any single day's aggregated active time, to contain
+    /// runaway background tracking. Session records are never clamped.
+    #[serde(default)]
+    pub daily_cap_ms: Option<u64>,
 }
 
 impl GrowthProfile {
     pub fn new() -> Self {
+        Self::new_with_id(Uuid::new_v4().to_string(), Utc::now())
+    }
+
+    /// Create a profile with a fixed id and creation time, for reproducible
+    /// fixtures and imports
+    pub fn new_with_id(id: String, created_at: DateTime<Utc>) -> Self {
         Self {
-            id: Uuid::new_v4().to_string(),
-            created_at: Utc::now(),
+            schema_version: CURRENT_SCHEMA_VERSION,
+            id,
+            created_at,
             sessions: Vec::new(),
             daily_aggregates: Vec::new(),
             lifetime_stats: LifetimeStats::default(),
+            freeze_tokens: 0,
+            frozen_dates: Vec::new(),
+            freeze_tokens_granted_after: None,
+            utc_offset_minutes: 0,
+            daily_cap_ms: None,
         }
     }
 
+    /// Add a session, ending it first if it was never ended so its duration
+    /// stops moving
     pub fn add_session(&mut self, session: Session) {
+        // End policy never rejects
+        let _ = self.add_session_with_policy(session, UnendedSessionPolicy::End);
+    }
+
+    /// Add a session, handling one that was never ended according to `policy`
+    pub fn add_session_with_policy(
+        &mut self,
+        mut session: Session,
+        policy: UnendedSessionPolicy,
+    ) -> Result<(), String> {
+        if session.ended_at.is_none() {
+            match policy {
+                UnendedSessionPolicy::End => session.end(),
+                UnendedSessionPolicy::Reject => {
+                    return Err(format!("session {} has not been ended", session.id));
+                }
+            }
+        }
+
+        self.insert_session(session);
+        Ok(())
+    }
+
+    fn insert_session(&mut self, session: Session) {
         // Create stored session
         let stored_session = StoredSession::new(session.clone());
 
-        // Update lifetime stats
-        self.lifetime_stats.update_from_session(&session);
+        if session.counts_toward_stats {
+            // Update lifetime stats
+            self.lifetime_stats.update_from_session(&session);
 
-        // Update or create daily aggregate
-        let session_date = session.started_at.date_naive();
-        if let Some(daily) = self.daily_aggregates.iter_mut().find(|d| d.date == session_date) {
-            daily.add_session(&session);
-        } else {
-            let mut daily = DailyAggregate::new(session_date);
-            daily.add_session(&session);
-            self.daily_aggregates.push(daily);
-        }
+            // Update or create daily aggregate
+            self.aggregate_session(&session);
 
-        // Recalculate streaks
-        self.lifetime_stats.recalculate_streaks(&self.daily_aggregates);
+            // Recalculate streaks, spending freeze tokens on single missed days
+            self.consume_freeze_tokens();
+            self.recalculate_streaks();
+        }
 
         // Add session
         self.sessions.push(stored_session);
     }
 
+    /// Add a session, or replace the stored session with the same id and
+    /// reconcile aggregates so re-sent sessions are not double-counted. New ids
+    /// go through `add_session`, so an un-ended one is ended first.
+    pub fn upsert_session(&mut self, session: Session) {
+        let Some(index) = self.sessions.iter().position(|s| s.session.id == session.id) else {
+            self.add_session(session);
+            return;
+        };
+
+        let old = self.sessions.remove(index).session;
+        if old.counts_toward_stats {
+            self.lifetime_stats.remove_session(&old);
+            let old_date = local_date(old.started_at, self.utc_offset_minutes);
+            if let Some(pos) = self.daily_aggregates.iter().position(|d| d.date == old_date) {
+                self.daily_aggregates[pos].remove_session(&old);
+                self.daily_aggregates[pos].apply_cap(self.daily_cap_ms);
+                if self.daily_aggregates[pos].sessions_count == 0 {
+                    self.daily_aggregates.remove(pos);
+                }
+            }
+        }
+
+        if session.counts_toward_stats {
+            self.lifetime_stats.update_from_session(&session);
+            self.aggregate_session(&session);
+        }
+        self.consume_freeze_tokens();
+        self.recalculate_streaks();
+        self.sessions.insert(index, StoredSession::new(session));
+    }
+
+    fn aggregate_session(&mut self, session: &Session) {
+        let session_date = local_date(session.started_at, self.utc_offset_minutes);
+        let index = match self.daily_aggregates.iter().position(|d| d.date == session_date) {
+            Some(index) => index,
+            None => {
+                self.daily_aggregates.push(DailyAggregate::new(session_date));
+                self.daily_aggregates.len() - 1
+            }
+        };
+
+        let daily = &mut self.daily_aggregates[index];
+        daily.add_session(session);
+        daily.apply_cap(self.daily_cap_ms);
+    }
+
+    /// Set or clear the per-day active time cap. Existing days are clamped
+    /// from their uncapped time, so raising or clearing the cap restores it.
+    pub fn set_daily_cap(&mut self, cap_ms: Option<u64>) {
+        self.daily_cap_ms = cap_ms;
+        for daily in &mut self.daily_aggregates {
+            daily.apply_cap(cap_ms);
+        }
+    }
+
+    /// Rebuild `daily_aggregates` from the retained sessions using a new UTC
+    /// offset, then recalculate streaks. Days whose sessions are no longer
+    /// retained in `sessions` are lost.
+    pub fn rebucket_daily_aggregates(&mut self, utc_offset_minutes: i32) {
+        self.utc_offset_minutes = utc_offset_minutes;
+        self.daily_aggregates.clear();
+
+        let sessions = std::mem::take(&mut self.sessions);
+        for stored_session in sessions.iter().filter(|s| s.session.counts_toward_stats) {
+            self.aggregate_session(&stored_session.session);
+        }
+        self.sessions = sessions;
+
+        self.recalculate_streaks();
+    }
+
+    /// Lifetime-style stats restricted to days in `start..=end`, with streaks
+    /// recomputed from only the in-range days
+    pub fn stats_for_range(&self, start: NaiveDate, end: NaiveDate) -> LifetimeStats {
+        let mut stats = LifetimeStats::default();
+        let in_range = |date: NaiveDate| date >= start && date <= end;
+
+        let days: Vec<DailyAggregate> = self
+            .daily_aggregates
+            .iter()
+            .filter(|d| in_range(d.date))
+            .cloned()
+            .collect();
+
+        for daily in &days {
+            stats.total_time_ms += daily.total_time_ms;
+            stats.total_keystrokes += daily.total_keystrokes as u64;
+            stats.total_sessions += daily.sessions_count;
+            stats.total_commits += daily.commits_count;
+            for (lang, time) in &daily.languages {
+                *stats.languages.entry(lang.clone()).or_insert(0) += time;
+            }
+        }
+
+        stats.total_pomodoros = self
+            .counted_sessions()
+            .filter(|s| in_range(local_date(s.session.started_at, self.utc_offset_minutes)))
+            .map(|s| s.session.pomodoro_intervals)
+            .sum();
+
+        let frozen: Vec<NaiveDate> =
+            self.frozen_dates.iter().copied().filter(|d| in_range(*d)).collect();
+        stats.recalculate_streaks_with_frozen(&days, &frozen, self.today());
+
+        stats
+    }
+
+    /// Commits from every session with a timestamp in `start..=end`, sorted by
+    /// time and deduplicated by hash across overlapping sessions
+    pub fn commits_in_range(&self, start: DateTime<Utc>, end: DateTime<Utc>) -> Vec<CommitRef> {
+        let mut commits: Vec<CommitRef> = self
+            .sessions
+            .iter()
+            .flat_map(|s| &s.session.commits)
+            .filter(|c| c.timestamp >= start && c.timestamp <= end)
+            .cloned()
+            .collect();
+        commits.sort_by_key(|c| c.timestamp);
+
+        let mut seen = std::collections::HashSet::new();
+        commits.retain(|c| seen.insert(c.hash.clone()));
+        commits
+    }
+
+    /// The session with the most active time; ties resolve to the earliest
+    pub fn longest_session(&self) -> Option<&StoredSession> {
+        self.counted_sessions().fold(None, |longest: Option<&StoredSession>, s| match longest {
+            Some(l)
+                if l.session.active_time_ms > s.session.active_time_ms
+                    || (l.session.active_time_ms == s.session.active_time_ms
+                        && l.session.started_at <= s.session.started_at) =>
+            {
+                Some(l)
+            }
+            _ => Some(s),
+        })
+    }
+
+    /// Merge duplicate daily aggregates, drop empty ones, and sort sessions
+    /// and aggregates by date
+    pub fn compact(&mut self) -> CompactionReport {
+        let mut report = CompactionReport {
+            bytes_before: serialized_len(self),
+            ..CompactionReport::default()
+        };
+
+        let mut aggregates = std::mem::take(&mut self.daily_aggregates);
+        aggregates.sort_by_key(|d| d.date);
+        for daily in aggregates {
+            match self.daily_aggregates.last_mut() {
+                Some(last) if last.date == daily.date => {
+                    last.merge(daily);
+                    last.apply_cap(self.daily_cap_ms);
+                    report.duplicate_aggregates_merged += 1;
+                }
+                _ => self.daily_aggregates.push(daily),
+            }
+        }
+
+        let before = self.daily_aggregates.len();
+        self.daily_aggregates.retain(|d| !d.is_empty());
+        report.empty_aggregates_removed = (before - self.daily_aggregates.len()) as u32;
+
+        self.sessions.sort_by_key(|s| s.session.started_at);
+        self.recalculate_streaks();
+
+        report.bytes_after = serialized_len(self);
+        report.bytes_saved = report.bytes_before.saturating_sub(report.bytes_after);
+        report
+    }
+
+    /// Mean interruptions per active hour across sessions with active time
+    pub fn average_interruptions_per_hour(&self) -> f32 {
+        let rates: Vec<f32> = self
+            .counted_sessions()
+            .filter(|s| s.session.active_time_ms > 0)
+            .map(|s| s.session.interruptions_per_hour())
+            .collect();
+        if rates.is_empty() {
+            return 0.0;
+        }
+        rates.iter().sum::<f32>() / rates.len() as f32
+    }
+
+    /// Start of the earliest retained session, or `created_at` if there are none
+    pub fn tracking_since(&self) -> DateTime<Utc> {
+        self.sessions
+            .iter()
+            .map(|s| s.session.started_at)
+            .min()
+            .unwrap_or(self.created_at)
+    }
+
+    /// Whole calendar days from `tracking_since` to today, in local time
+    pub fn days_tracked(&self) -> i64 {
+        (self.today() - local_date(self.tracking_since(), self.utc_offset_minutes)).num_days()
+    }
+
+    /// Today's date in the profile's local time (`utc_offset_minutes`)
+    pub fn today(&self) -> NaiveDate {
+        local_date(Utc::now(), self.utc_offset_minutes)
+    }
+
+    /// Retained sessions that count toward stats
+    pub fn counted_sessions(&self) -> impl Iterator<Item = &StoredSession> {
+        self.sessions.iter().filter(|s| s.session.counts_toward_stats)
+    }
+
+    /// Sorted, deduplicated dates that have a daily aggregate
+    pub fn active_dates(&self) -> Vec<NaiveDate> {
+        let mut dates: Vec<_> = self.daily_aggregates.iter().map(|d| d.date).collect();
+        dates.sort();
+        dates.dedup();
+        dates
+    }
+
+    fn recalculate_streaks(&mut self) {
+        let mut dates = self.active_dates();
+        dates.extend_from_slice(&self.frozen_dates);
+        let today = self.today();
+        self.lifetime_stats.set_streaks_from_dates(dates, today);
+    }
+
+    /// Grant additional streak freeze tokens. They only cover days missed
+    /// after the latest active day at the time of the first outstanding grant.
+    pub fn grant_freeze_tokens(&mut self, count: u32) {
+        if self.freeze_tokens == 0 {
+            self.freeze_tokens_granted_after = self.active_dates().last().copied();
+        }
+        self.freeze_tokens += count;
+    }
+
+    /// Spend a token on the single missed day just before the current streak,
+    /// if that day was missed after the tokens were granted
+    fn consume_freeze_tokens(&mut self) {
+        if self.freeze_tokens == 0 {
+            return;
+        }
+
+        let mut dates = self.active_dates();
+        dates.extend_from_slice(&self.frozen_dates);
+        dates.sort();
+        dates.dedup();
+
+        let Some(&streak_start) = current_run(&dates, self.today()).first() else {
+            return;
+        };
+        let missed = streak_start - chrono::Duration::days(1);
+        let before_missed = missed - chrono::Duration::days(1);
+        let granted_before_miss = self.freeze_tokens_granted_after.is_none_or(|d| missed > d);
+        if granted_before_miss && dates.binary_search(&before_missed).is_ok() {
+            self.frozen_dates.push(missed);
+            self.freeze_tokens -= 1;
+        }
+    }
+
+    /// Look up the aggregate for a single day
+    pub fn daily_aggregate(&self, date: NaiveDate) -> Option<&DailyAggregate> {
+        self.daily_aggregates.iter().find(|d| d.date == date)
+    }
+
     pub fn to_json(&self) -> Result<String, serde_json::Error> {
         serde_json::to_string(self)
     }
@@ -225,6 +955,82 @@ impl GrowthProfile {
     }
 }
 
+fn serialized_len(profile: &GrowthProfile) -> u64 {
+    serde_json::to_vec(profile).map_or(0, |bytes| bytes.len() as u64)
+}
+
+/// Upgrade a profile JSON blob of any older schema version
+pub fn migrate(json: &str) -> Result<GrowthProfile, String> {
+    migrate_with_report(json).map(|(profile, _)| profile)
+}
+
+/// Upgrade a profile JSON blob, returning human-readable notes for each
+/// migration step that was applied
+pub fn migrate_with_report(json: &str) -> Result<(GrowthProfile, Vec<String>), String> {
+    let mut value: serde_json::Value =
+        serde_json::from_str(json).map_err(|e| format!("invalid profile JSON: {}", e))?;
+
+    let version = value
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(0) as u32;
+    if version > CURRENT_SCHEMA_VERSION {
+        return Err(format!(
+            "profile schema version {} is newer than supported version {}",
+            version, CURRENT_SCHEMA_VERSION
+        ));
+    }
+
+    let mut notes = Vec::new();
+    if version < 1 {
+        notes.extend(migrate_v0_to_v1(&mut value)?);
+    }
+
+    let mut profile: GrowthProfile =
+        serde_json::from_value(value).map_err(|e| format!("invalid profile: {}", e))?;
+    profile.schema_version = CURRENT_SCHEMA_VERSION;
+
+    Ok((profile, notes))
+}
+
+fn migrate_v0_to_v1(value: &mut serde_json::Value) -> Result<Vec<String>, String> {
+    let profile = value
+        .as_object_mut()
+        .ok_or_else(|| "profile JSON must be an object".to_string())?;
+
+    let mut notes = vec!["upgraded profile from schema version 0 to 1".to_string()];
+
+    if !profile.contains_key("freeze_tokens") {
+        profile.insert("freeze_tokens".to_string(), 0.into());
+        profile.insert("frozen_dates".to_string(), serde_json::json!([]));
+        notes.push("added streak freeze tokens, defaulted to 0".to_string());
+    }
+
+    let stats = profile.get_mut("lifetime_stats").and_then(|s| s.as_object_mut());
+    if let Some(stats) = stats {
+        if !stats.contains_key("total_pomodoros") {
+            stats.insert("total_pomodoros".to_string(), 0.into());
+            notes.push("added pomodoro tracking, defaulted to 0".to_string());
+        }
+    }
+
+    let sessions = profile.get("sessions").and_then(|s| s.as_array());
+    let session_lacks_fields = sessions.into_iter().flatten().any(|stored| {
+        stored.get("session").and_then(|s| s.as_object()).is_some_and(|session| {
+            !session.contains_key("raw_keystroke_count") || !session.contains_key("file_languages")
+        })
+    });
+    if session_lacks_fields {
+        notes.push(
+            "added keystroke burst clamping and per-file language attribution to sessions, \
+             defaulted to empty"
+                .to_string(),
+        );
+    }
+
+    Ok(notes)
+}
+
 impl Default for GrowthProfile {
     fn default() -> Self {
         Self::new()
@@ -255,6 +1061,679 @@ mod tests {
         assert_eq!(profile.daily_aggregates.len(), 1);
     }
 
+    #[test]
+    fn test_upsert_session_replaces_previous_totals() {
+        let mut profile = GrowthProfile::new();
+        let mut session = Session::new(7);
+        for _ in 0..5 {
+            session.record_keystroke();
+        }
+        profile.upsert_session(session.clone());
+        assert_eq!(profile.lifetime_stats.total_keystrokes, 5);
+
+        session.keystroke_count = 12;
+        profile.upsert_session(session);
+
+        assert_eq!(profile.sessions.len(), 1);
+        assert_eq!(profile.sessions[0].session.keystroke_count, 12);
+        assert_eq!(profile.lifetime_stats.total_keystrokes, 12);
+        assert_eq!(profile.lifetime_stats.total_sessions, 1);
+        assert_eq!(profile.daily_aggregates.len(), 1);
+        assert_eq!(profile.daily_aggregates[0].total_keystrokes, 12);
+        assert_eq!(profile.daily_aggregates[0].sessions_count, 1);
+    }
+
+    #[test]
+    fn test_upsert_new_session_ends_it() {
+        let mut profile = GrowthProfile::new();
+        profile.upsert_session(Session::new(3));
+
+        let stored = &profile.sessions[0];
+        assert!(stored.session.ended_at.is_some());
+        assert!(stored.session.final_stats.is_some());
+        assert_eq!(
+            stored.computed_stats.total_duration_ms,
+            stored.session.final_stats.as_ref().unwrap().total_duration_ms
+        );
+    }
+
+    #[test]
+    fn test_non_counting_session_skips_stats() {
+        let mut profile = GrowthProfile::new();
+        let mut counted = Session::new(1);
+        counted.active_time_ms = 60_000;
+        profile.add_session(counted);
+
+        let mut demo = Session::new(2);
+        demo.active_time_ms = 30 * 60_000;
+        demo.counts_toward_stats = false;
+        profile.add_session(demo);
+
+        assert_eq!(profile.sessions.len(), 2);
+        assert_eq!(profile.lifetime_stats.total_sessions, 1);
+        assert_eq!(profile.lifetime_stats.total_time_ms, 60_000);
+        assert_eq!(profile.daily_aggregates[0].sessions_count, 1);
+    }
+
+    #[test]
+    fn test_commits_in_range() {
+        let now = Utc::now();
+        let commit = |hash: &str, hours_ago: i64| {
+            CommitRef::new(
+                hash.to_string(),
+                format!("commit {}", hash),
+                now - chrono::Duration::hours(hours_ago),
+                Vec::new(),
+            )
+        };
+
+        let mut profile = GrowthProfile::new();
+        let mut first = Session::new(1);
+        first.commits = vec![commit("aaa", 50), commit("bbb", 10)];
+        profile.add_session(first);
+        let mut second = Session::new(2);
+        second.commits = vec![commit("ccc", 2), commit("bbb", 10)];
+        profile.add_session(second);
+
+        let commits = profile.commits_in_range(now - chrono::Duration::hours(24), now);
+        let hashes: Vec<&str> = commits.iter().map(|c| c.hash.as_str()).collect();
+        assert_eq!(hashes, vec!["bbb", "ccc"]);
+    }
+
+    #[test]
+    fn test_average_interruptions_per_hour() {
+        let mut profile = GrowthProfile::new();
+        assert_eq!(profile.average_interruptions_per_hour(), 0.0);
+
+        for idles in [1, 3] {
+            let mut session = Session::new(idles as u64);
+            session.active_time_ms = 60 * 60 * 1000;
+            for _ in 0..idles {
+                session.idle_periods.push(crate::session::IdlePeriod::new(Utc::now()));
+            }
+            profile.add_session(session);
+        }
+        profile.add_session(Session::new(9));
+
+        assert!((profile.average_interruptions_per_hour() - 2.0).abs() < 0.001);
+        assert!((profile.sessions[1].computed_stats.interruptions_per_hour - 3.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_average_interruptions_per_hour_skips_non_counting() {
+        let mut profile = GrowthProfile::new();
+        let mut counted = Session::new(1);
+        counted.active_time_ms = 60 * 60 * 1000;
+        counted.idle_periods.push(crate::session::IdlePeriod::new(Utc::now()));
+        profile.add_session(counted);
+
+        let mut demo = Session::new(2);
+        demo.active_time_ms = 60 * 60 * 1000;
+        demo.counts_toward_stats = false;
+        for _ in 0..9 {
+            demo.idle_periods.push(crate::session::IdlePeriod::new(Utc::now()));
+        }
+        profile.add_session(demo);
+
+        assert!((profile.average_interruptions_per_hour() - 1.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_session_comparison_skips_non_counting() {
+        let mut profile = GrowthProfile::new();
+        let mut counted = Session::new(1);
+        counted.active_time_ms = 10 * 60_000;
+        counted.keystroke_count = 500;
+        profile.add_session(counted.clone());
+
+        let mut demo = Session::new(2);
+        demo.active_time_ms = 60_000;
+        demo.keystroke_count = 5000;
+        demo.counts_toward_stats = false;
+        profile.add_session(demo);
+
+        let comparison = SessionComparison::compare(&counted, &profile);
+        assert!(comparison.keystroke_density_diff.abs() < 0.001);
+    }
+
+    #[test]
+    fn test_compact_cleans_messy_aggregates() {
+        let mut profile = GrowthProfile::new();
+        let day = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let mut first = DailyAggregate::new(day);
+        first.total_time_ms = 1000;
+        first.sessions_count = 1;
+        let mut duplicate = first.clone();
+        duplicate.languages.insert("rust".to_string(), 500);
+        let empty = DailyAggregate::new(day.pred_opt().unwrap());
+        profile.daily_aggregates = vec![duplicate, empty, first];
+
+        let mut later = Session::new(2);
+        later.started_at = Utc::now();
+        let mut earlier = Session::new(1);
+        earlier.started_at = later.started_at - chrono::Duration::days(1);
+        profile.sessions = vec![StoredSession::new(later), StoredSession::new(earlier)];
+
+        let report = profile.compact();
+
+        assert_eq!(report.duplicate_aggregates_merged, 1);
+        assert_eq!(report.empty_aggregates_removed, 1);
+        assert!(report.bytes_saved > 0);
+        assert_eq!(report.bytes_before - report.bytes_after, report.bytes_saved);
+        assert_eq!(profile.daily_aggregates.len(), 1);
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, 2000);
+        assert_eq!(profile.daily_aggregates[0].sessions_count, 2);
+        assert_eq!(profile.daily_aggregates[0].languages.get("rust"), Some(&500));
+        assert_eq!(profile.sessions[0].session.id, 1);
+    }
+
+    #[test]
+    fn test_active_dates_sorted_and_deduped() {
+        let mut profile = GrowthProfile::new();
+        assert!(profile.active_dates().is_empty());
+
+        let date = |day| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        for day in [9, 2, 9, 5] {
+            profile.daily_aggregates.push(DailyAggregate::new(date(day)));
+        }
+
+        assert_eq!(profile.active_dates(), vec![date(2), date(5), date(9)]);
+    }
+
+    #[test]
+    fn test_add_session_ends_unended_session() {
+        let mut profile = GrowthProfile::new();
+        let session = Session::new(1);
+        assert!(session.ended_at.is_none());
+
+        let err = profile
+            .add_session_with_policy(session.clone(), UnendedSessionPolicy::Reject)
+            .unwrap_err();
+        assert!(err.contains("not been ended"));
+        assert!(profile.sessions.is_empty());
+
+        profile.add_session(session);
+        let stored = &profile.sessions[0];
+        let ended_at = stored.session.ended_at.expect("session should be stamped");
+        assert!(ended_at >= stored.session.started_at);
+        assert!(stored.session.final_stats.is_some());
+    }
+
+    #[test]
+    fn test_year_in_review_scoped_to_year() {
+        let mut profile = GrowthProfile::new();
+        let entries = [
+            ((2023, 12, 31), "python", 5 * 3_600_000, 4),
+            ((2024, 1, 1), "rust", 3_600_000, 1),
+            ((2024, 1, 2), "rust", 2 * 3_600_000, 0),
+            ((2024, 3, 10), "go", 1_800_000, 2),
+            ((2025, 1, 1), "python", 9 * 3_600_000, 3),
+        ];
+        for ((y, m, d), lang, time_ms, commits) in entries {
+            let mut daily = DailyAggregate::new(NaiveDate::from_ymd_opt(y, m, d).unwrap());
+            daily.total_time_ms = time_ms;
+            daily.commits_count = commits;
+            daily.sessions_count = 1;
+            daily.languages.insert(lang.to_string(), time_ms);
+            profile.daily_aggregates.push(daily);
+        }
+
+        let review = year_in_review(&profile, 2024);
+        assert_eq!(review.total_time_ms, 3 * 3_600_000 + 1_800_000);
+        assert!((review.total_hours - 3.5).abs() < 0.001);
+        assert_eq!(review.active_days, 3);
+        assert_eq!(review.longest_streak, 2);
+        assert_eq!(review.top_languages, vec!["rust", "go"]);
+        assert_eq!(review.total_commits, 3);
+        assert_eq!(review.best_month, Some(1));
+        assert_eq!(review.busiest_day, NaiveDate::from_ymd_opt(2024, 1, 2));
+
+        let empty = year_in_review(&profile, 2020);
+        assert_eq!(empty.active_days, 0);
+        assert_eq!(empty.best_month, None);
+        assert_eq!(empty.busiest_day, None);
+    }
+
+    #[test]
+    fn test_daily_cap_clamps_day_total() {
+        let mut profile = GrowthProfile::new();
+        profile.set_daily_cap(Some(8 * 3_600_000));
+
+        let start = Utc::now() - chrono::Duration::hours(12);
+        for id in 1..=2 {
+            let mut session = Session::new_at(id, start);
+            session.active_time_ms = 5 * 3_600_000;
+            profile.add_session(session);
+        }
+
+        assert_eq!(profile.daily_aggregates.len(), 1);
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, 8 * 3_600_000);
+        assert!(profile.sessions.iter().all(|s| s.session.active_time_ms == 5 * 3_600_000));
+
+        profile.set_daily_cap(Some(3_600_000));
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, 3_600_000);
+
+        // Raising or clearing the cap brings the clamped time back
+        profile.set_daily_cap(Some(9 * 3_600_000));
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, 9 * 3_600_000);
+        profile.set_daily_cap(None);
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, 10 * 3_600_000);
+    }
+
+    #[test]
+    fn test_upsert_session_under_daily_cap() {
+        let minutes = |m: u64| m * 60_000;
+        let mut profile = GrowthProfile::new();
+        profile.set_daily_cap(Some(minutes(60)));
+
+        let start = Utc::now() - chrono::Duration::hours(1);
+        let mut a = Session::new_at(1, start);
+        a.active_time_ms = minutes(30);
+        let mut b = Session::new_at(2, start);
+        b.active_time_ms = minutes(40);
+        profile.upsert_session(a);
+        profile.upsert_session(b.clone());
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, minutes(60));
+
+        b.active_time_ms = minutes(10);
+        profile.upsert_session(b);
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, minutes(40));
+
+        // Merging duplicate days during compaction stays under the cap
+        let mut duplicate = profile.daily_aggregates[0].clone();
+        duplicate.sessions_count = 1;
+        profile.daily_aggregates.push(duplicate);
+        profile.compact();
+        assert_eq!(profile.daily_aggregates.len(), 1);
+        assert_eq!(profile.daily_aggregates[0].total_time_ms, minutes(60));
+        assert_eq!(profile.daily_aggregates[0].uncapped_time(), minutes(80));
+    }
+
+    #[test]
+    fn test_tracking_since_and_days_tracked() {
+        let mut profile = GrowthProfile::new();
+        assert_eq!(profile.tracking_since(), profile.created_at);
+        assert_eq!(profile.days_tracked(), 0);
+
+        for days_ago in [3, 10, 1] {
+            let started_at = Utc::now() - chrono::Duration::days(days_ago);
+            profile.add_session(Session::new_at(days_ago as u64, started_at));
+        }
+
+        assert_eq!(profile.tracking_since(), profile.sessions[1].session.started_at);
+        assert_eq!(profile.days_tracked(), 10);
+    }
+
+    #[test]
+    fn test_flag_anomalous_sessions() {
+        let start = Utc::now() - chrono::Duration::hours(2);
+        let mut profile = GrowthProfile::new();
+
+        let mut normal = Session::new_at(1, start);
+        normal.ended_at = Some(start + chrono::Duration::hours(1));
+        normal.active_time_ms = 30 * 60 * 1000;
+        normal.keystroke_count = 6000;
+
+        let mut overlong = normal.clone();
+        overlong.id = 2;
+        overlong.active_time_ms = 2 * 3_600_000;
+
+        let mut reversed = normal.clone();
+        reversed.id = 3;
+        reversed.ended_at = Some(start - chrono::Duration::minutes(5));
+        reversed.active_time_ms = 0;
+
+        let mut too_fast = normal.clone();
+        too_fast.id = 4;
+        too_fast.active_time_ms = 60 * 1000;
+        too_fast.keystroke_count = 5001;
+
+        let mut no_active = normal.clone();
+        no_active.id = 5;
+        no_active.active_time_ms = 0;
+        no_active.keystroke_count = 4000;
+
+        profile.sessions = [normal, overlong, reversed, too_fast, no_active]
+            .into_iter()
+            .map(StoredSession::new)
+            .collect();
+
+        let flagged = flag_anomalous_sessions(&profile);
+        assert_eq!(
+            flagged,
+            vec![
+                (2, "active time exceeds total duration".to_string()),
+                (3, "ended before it started".to_string()),
+                (3, "6000 keystrokes with no active time".to_string()),
+                (4, "typing speed of 1000 WPM".to_string()),
+                (5, "4000 keystrokes with no active time".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_streak_recovery_hint() {
+        let today = NaiveDate::from_ymd_opt(2024, 4, 20).unwrap();
+        let mut profile = GrowthProfile::new();
+        for days_ago in [2, 3, 4, 5, 8] {
+            let date = today - chrono::Duration::days(days_ago);
+            profile.daily_aggregates.push(DailyAggregate::new(date));
+        }
+
+        // Without a token the missed day cannot be bridged
+        assert!(streak_recovery_hint_on(&profile, today).is_none());
+        profile.freeze_tokens = 1;
+        profile.freeze_tokens_granted_after = Some(today - chrono::Duration::days(1));
+        assert!(streak_recovery_hint_on(&profile, today).is_none());
+        profile.freeze_tokens_granted_after = Some(today - chrono::Duration::days(2));
+
+        let hint = streak_recovery_hint_on(&profile, today).unwrap();
+        assert_eq!(hint.missed_date, today - chrono::Duration::days(1));
+        assert_eq!(hint.broken_streak, 4);
+        assert_eq!(hint.restored_streak, 6);
+
+        // Still alive today or yesterday: nothing to recover
+        assert!(streak_recovery_hint_on(&profile, today - chrono::Duration::days(1)).is_none());
+        // Lapsed for two days: too late
+        assert!(streak_recovery_hint_on(&profile, today + chrono::Duration::days(1)).is_none());
+    }
+
+    #[test]
+    fn test_pomodoros_flow_into_lifetime_stats() {
+        let mut profile = GrowthProfile::new();
+        let mut session = Session::new(1);
+        for _ in 0..3 {
+            session.complete_pomodoro();
+        }
+        assert_eq!(SessionStats::from_session(&session).pomodoro_intervals, 3);
+
+        profile.add_session(session);
+        assert_eq!(profile.sessions[0].computed_stats.pomodoro_intervals, 3);
+        assert_eq!(profile.lifetime_stats.total_pomodoros, 3);
+    }
+
+    #[test]
+    fn test_daily_aggregate_lookup() {
+        let mut profile = GrowthProfile::new();
+        let session = Session::new(1);
+        let date = session.started_at.date_naive();
+        profile.add_session(session);
+
+        let daily = profile.daily_aggregate(date).unwrap();
+        assert_eq!(daily.sessions_count, 1);
+        assert!(profile.daily_aggregate(date - chrono::Duration::days(1)).is_none());
+    }
+
+    #[test]
+    fn test_goal_streak() {
+        let mut profile = GrowthProfile::new();
+        let today = Utc::now().date_naive();
+        let goal_ms = 30 * 60 * 1000;
+        for (days_ago, minutes) in [(0, 45), (1, 10), (2, 40), (3, 35), (4, 50)] {
+            let mut daily = DailyAggregate::new(today - chrono::Duration::days(days_ago));
+            daily.total_time_ms = minutes * 60 * 1000;
+            profile.daily_aggregates.push(daily);
+        }
+        profile.lifetime_stats.recalculate_streaks(&profile.daily_aggregates, profile.today());
+
+        assert_eq!(profile.lifetime_stats.current_streak, 5);
+        assert_eq!(goal_streak(&profile, goal_ms), (1, 3));
+    }
+
+    #[test]
+    fn test_freeze_token_preserves_streak() {
+        let today = Utc::now().date_naive();
+        let session_on = |days_ago: i64| {
+            let mut session = Session::new(days_ago as u64);
+            session.started_at = Utc::now() - chrono::Duration::days(days_ago);
+            session
+        };
+
+        let mut profile = GrowthProfile::new();
+        profile.grant_freeze_tokens(1);
+        for days_ago in [3, 2, 0] {
+            profile.add_session(session_on(days_ago));
+        }
+
+        assert_eq!(profile.freeze_tokens, 0);
+        assert_eq!(profile.frozen_dates, vec![today - chrono::Duration::days(1)]);
+        assert_eq!(profile.lifetime_stats.current_streak, 4);
+
+        let mut unprotected = GrowthProfile::new();
+        for days_ago in [3, 2, 0] {
+            unprotected.add_session(session_on(days_ago));
+        }
+        assert_eq!(unprotected.lifetime_stats.current_streak, 1);
+    }
+
+    #[test]
+    fn test_freeze_token_only_bridges_fresh_miss() {
+        let today = Utc::now().date_naive();
+        let session_on = |days_ago: i64| {
+            Session::new_at(days_ago as u64, Utc::now() - chrono::Duration::days(days_ago))
+        };
+
+        // An old single-day gap at 9 days ago exists before the grant
+        let mut profile = GrowthProfile::new();
+        for days_ago in [10, 8, 3] {
+            profile.add_session(session_on(days_ago));
+        }
+        profile.grant_freeze_tokens(1);
+        assert!(profile.frozen_dates.is_empty());
+
+        // Missing 2 days ago, then coding again, spends the token on that day
+        for days_ago in [1, 0] {
+            profile.add_session(session_on(days_ago));
+        }
+
+        assert_eq!(profile.freeze_tokens, 0);
+        assert_eq!(profile.frozen_dates, vec![today - chrono::Duration::days(2)]);
+        assert_eq!(profile.lifetime_stats.current_streak, 4);
+        assert_eq!(profile.lifetime_stats.longest_streak, 4);
+    }
+
+    #[test]
+    fn test_migrate_with_report() {
+        let mut blob = serde_json::to_value(GrowthProfile::new()).unwrap();
+        let fields = blob.as_object_mut().unwrap();
+        fields.remove("schema_version");
+        fields.remove("freeze_tokens");
+        fields.remove("frozen_dates");
+        fields["lifetime_stats"].as_object_mut().unwrap().remove("total_pomodoros");
+
+        let (profile, notes) = migrate_with_report(&blob.to_string()).unwrap();
+        assert_eq!(profile.schema_version, CURRENT_SCHEMA_VERSION);
+        assert_eq!(profile.freeze_tokens, 0);
+        assert!(notes.iter().any(|n| n.contains("freeze tokens, defaulted to 0")));
+        assert!(notes.iter().any(|n| n.contains("pomodoro tracking, defaulted to 0")));
+        assert!(!notes.iter().any(|n| n.contains("keystroke burst clamping")));
+
+        let mut profile = GrowthProfile::new();
+        profile.add_session(Session::new(1));
+        let mut blob = serde_json::to_value(&profile).unwrap();
+        blob.as_object_mut().unwrap().remove("schema_version");
+        let (_, notes) = migrate_with_report(&blob.to_string()).unwrap();
+        assert!(!notes.iter().any(|n| n.contains("keystroke burst clamping")));
+
+        let session = blob["sessions"][0]["session"].as_object_mut().unwrap();
+        session.remove("raw_keystroke_count");
+        session.remove("file_languages");
+        let (profile, notes) = migrate_with_report(&blob.to_string()).unwrap();
+        assert!(notes.iter().any(|n| n.contains("keystroke burst clamping")));
+        assert_eq!(profile.sessions[0].session.raw_keystroke_count, 0);
+
+        let (_, notes) = migrate_with_report(&GrowthProfile::new().to_json().unwrap()).unwrap();
+        assert!(notes.is_empty());
+    }
+
+    #[test]
+    fn test_streak_uses_local_today() {
+        use chrono::Timelike;
+
+        // An offset that puts local time an hour into the previous UTC day
+        let now = Utc::now();
+        let offset = -((now.hour() * 60 + now.minute()) as i32 + 60);
+
+        let mut profile = GrowthProfile::new();
+        profile.utc_offset_minutes = offset;
+        profile.add_session(Session::new_at(1, now - chrono::Duration::days(1)));
+
+        let local_today = profile.today();
+        assert_eq!(local_today, now.date_naive().pred_opt().unwrap());
+        assert_eq!(profile.active_dates(), vec![local_today.pred_opt().unwrap()]);
+        assert_eq!(profile.lifetime_stats.current_streak, 1);
+        assert_eq!(current_streak_dates(&profile).len(), 1);
+    }
+
+    #[test]
+    fn test_rebucket_daily_aggregates() {
+        let mut profile = GrowthProfile::new();
+        let mut session = Session::new(1);
+        session.started_at = DateTime::parse_from_rfc3339("2024-03-10T23:30:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        profile.add_session(session);
+
+        let utc_day = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let next_day = NaiveDate::from_ymd_opt(2024, 3, 11).unwrap();
+        assert!(profile.daily_aggregate(utc_day).is_some());
+
+        profile.rebucket_daily_aggregates(120);
+        assert_eq!(profile.daily_aggregates.len(), 1);
+        assert!(profile.daily_aggregate(utc_day).is_none());
+        assert_eq!(profile.daily_aggregate(next_day).unwrap().sessions_count, 1);
+    }
+
+    #[test]
+    fn test_longest_session() {
+        let mut profile = GrowthProfile::new();
+        assert!(profile.longest_session().is_none());
+
+        for (id, active_ms) in [(1, 60_000), (2, 300_000), (3, 300_000)] {
+            let mut session = Session::new(id);
+            session.active_time_ms = active_ms;
+            session.started_at = Utc::now() - chrono::Duration::hours(10 - id as i64);
+            profile.add_session(session);
+        }
+
+        assert_eq!(profile.longest_session().unwrap().session.id, 2);
+    }
+
+    #[test]
+    fn test_longest_session_skips_non_counting() {
+        let mut profile = GrowthProfile::new();
+        let mut counted = Session::new(1);
+        counted.active_time_ms = 60_000;
+        profile.add_session(counted);
+
+        let mut demo = Session::new(2);
+        demo.active_time_ms = 10 * 60_000;
+        demo.counts_toward_stats = false;
+        profile.add_session(demo);
+
+        assert_eq!(profile.longest_session().unwrap().session.id, 1);
+    }
+
+    #[test]
+    fn test_weekly_goal_pace() {
+        let mut profile = GrowthProfile::new();
+        let wednesday = NaiveDate::from_ymd_opt(2024, 1, 17).unwrap();
+        for (date, hours) in [((2024, 1, 12), 5), ((2024, 1, 15), 2), ((2024, 1, 16), 3)] {
+            let date = NaiveDate::from_ymd_opt(date.0, date.1, date.2).unwrap();
+            let mut daily = DailyAggregate::new(date);
+            daily.total_time_ms = hours * 3600 * 1000;
+            profile.daily_aggregates.push(daily);
+        }
+
+        let pace = weekly_goal_pace_on(&profile, 10 * 3600 * 1000, wednesday);
+        assert_eq!(pace.completed_ms, 5 * 3600 * 1000);
+        assert_eq!(pace.remaining_ms, 5 * 3600 * 1000);
+        assert_eq!(pace.days_left, 5);
+        assert_eq!(pace.suggested_daily_ms, 3600 * 1000);
+
+        let met = weekly_goal_pace_on(&profile, 3600 * 1000, wednesday);
+        assert_eq!(met.remaining_ms, 0);
+        assert_eq!(met.suggested_daily_ms, 0);
+    }
+
+    #[test]
+    fn test_deterministic_profile() {
+        let created_at = DateTime::parse_from_rfc3339("2024-01-15T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let build = || {
+            let mut profile = GrowthProfile::new_with_id("fixture".to_string(), created_at);
+            let mut session = Session::new_at(1, created_at);
+            session.ended_at = Some(created_at + chrono::Duration::minutes(30));
+            let files = [("main.rs", "rust"), ("app.ts", "typescript"), ("lib.py", "python")];
+            for (i, (file, language)) in files.iter().enumerate() {
+                let time_ms = (i as u64 + 1) * 60_000;
+                session.file_languages.insert(file.to_string(), language.to_string());
+                session.file_times.insert(file.to_string(), time_ms);
+                session.languages.insert(language.to_string(), time_ms);
+            }
+            for kind in [ActivityKind::Debug, ActivityKind::Edit, ActivityKind::Build] {
+                session.activity_times.insert(kind, 60_000);
+            }
+            profile.add_session(session);
+            profile
+        };
+
+        let first = build().to_json().unwrap();
+        assert_eq!(first, build().to_json().unwrap());
+        assert!(first.contains("\"id\":\"fixture\""));
+        // Map keys serialize in sorted order regardless of insertion order
+        let languages = r#""languages":{"python":180000,"rust":60000,"typescript":120000}"#;
+        assert!(first.contains(languages));
+        let activity_times = r#""activity_times":{"edit":60000,"build":60000,"debug":60000}"#;
+        assert!(first.contains(activity_times));
+    }
+
+    #[test]
+    fn test_longest_inactivity_gap() {
+        let mut profile = GrowthProfile::new();
+        let date = |day| NaiveDate::from_ymd_opt(2024, 3, day).unwrap();
+        profile.daily_aggregates.push(DailyAggregate::new(date(1)));
+        assert_eq!(longest_inactivity_gap(&profile), None);
+
+        for day in [2, 3, 6, 21, 22] {
+            profile.daily_aggregates.push(DailyAggregate::new(date(day)));
+        }
+
+        assert_eq!(longest_inactivity_gap(&profile), Some((date(7), date(20), 14)));
+    }
+
+    #[test]
+    fn test_stats_for_range() {
+        let mut profile = GrowthProfile::new();
+        for days_ago in [0, 1, 2, 10] {
+            let mut session = Session::new(days_ago as u64);
+            session.started_at = Utc::now() - chrono::Duration::days(days_ago);
+            session.keystroke_count = 100;
+            session.active_time_ms = 60_000;
+            session.record_file_edit("main.rs".to_string(), "rust".to_string());
+            session.complete_pomodoro();
+            profile.add_session(session);
+        }
+
+        let today = Utc::now().date_naive();
+        let full = profile.stats_for_range(today - chrono::Duration::days(30), today);
+        let lifetime = &profile.lifetime_stats;
+        assert_eq!(full.total_time_ms, lifetime.total_time_ms);
+        assert_eq!(full.total_keystrokes, lifetime.total_keystrokes);
+        assert_eq!(full.total_sessions, lifetime.total_sessions);
+        assert_eq!(full.total_pomodoros, lifetime.total_pomodoros);
+        assert_eq!(full.languages, lifetime.languages);
+        assert_eq!(full.current_streak, lifetime.current_streak);
+        assert_eq!(full.longest_streak, lifetime.longest_streak);
+
+        let narrow = profile.stats_for_range(today - chrono::Duration::days(1), today);
+        assert_eq!(narrow.total_sessions, 2);
+        assert_eq!(narrow.total_pomodoros, 2);
+        assert_eq!(narrow.total_keystrokes, 200);
+        assert_eq!(narrow.longest_streak, 2);
+    }
+
     #[test]
     fn test_json_serialization() {
         let profile = GrowthProfile::new();
@@ -1,8 +1,8 @@
-use chrono::{Datelike, Duration, NaiveDate, Utc, Weekday, Timelike};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Timelike, Utc, Weekday};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
-use crate::storage::GrowthProfile;
+use crate::storage::{local_date, GrowthProfile};
 
 /// Single cell in the activity heatmap
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -12,6 +12,7 @@ pub struct HeatmapCell {
     pub hour: u8,         // 0-23
     pub intensity: f32,   // 0.0-1.0 normalized
     pub raw_minutes: u32,
+    pub date: NaiveDate,
 }
 
 /// Complete heatmap dataset
@@ -30,13 +31,27 @@ pub struct LanguageStat {
     pub time_ms: u64,
     pub files_count: u32,
     pub percentage: f32,
+    /// Share of all distinct edited files recorded with this language
+    #[serde(default)]
+    pub files_percentage: f32,
     pub color: String,
 }
 
+/// Largest supported heatmap span: a full year plus a partial week
+pub const MAX_HEATMAP_WEEKS: u8 = 53;
+
+/// Clamp a requested heatmap span to the supported `1..=MAX_HEATMAP_WEEKS` range
+pub fn clamp_weeks(weeks: u8) -> u8 {
+    weeks.clamp(1, MAX_HEATMAP_WEEKS)
+}
+
 /// Generate a heatmap of activity over time
+///
+/// `weeks` is clamped to `1..=MAX_HEATMAP_WEEKS`.
 pub fn generate_heatmap(profile: &GrowthProfile, weeks: u8) -> HeatmapData {
+    let weeks = clamp_weeks(weeks);
     let mut cells = Vec::new();
-    let today = Utc::now().date_naive();
+    let today = profile.today();
     let start_date = today - Duration::weeks(weeks as i64);
 
     // Build a map of date -> total minutes
@@ -71,6 +86,7 @@ pub fn generate_heatmap(profile: &GrowthProfile, weeks: u8) -> HeatmapData {
                     hour: 0, // Not used for daily heatmap
                     intensity,
                     raw_minutes,
+                    date,
                 });
             }
         }
@@ -84,50 +100,293 @@ pub fn generate_heatmap(profile: &GrowthProfile, weeks: u8) -> HeatmapData {
     }
 }
 
-/// Generate hourly distribution of activity (0-23 hours)
-pub fn generate_hourly_distribution(profile: &GrowthProfile) -> HashMap<u8, u64> {
+/// Set the minutes for `date` in an existing heatmap without regenerating it,
+/// renormalizing intensities if the maximum changed. Dates outside the
+/// heatmap window ending at `today` are ignored.
+pub fn update_heatmap_cell(
+    heatmap: &mut HeatmapData,
+    date: NaiveDate,
+    minutes: u32,
+    today: NaiveDate,
+) {
+    let start_date = today - Duration::weeks(heatmap.weeks as i64);
+    if date < start_date || date > today {
+        return;
+    }
+    let Some(index) = heatmap.cells.iter().position(|c| c.date == date) else {
+        return;
+    };
+
+    let previous_max = heatmap.max_minutes;
+    heatmap.cells[index].raw_minutes = minutes;
+    heatmap.total_minutes = heatmap.cells.iter().map(|c| c.raw_minutes).sum();
+    heatmap.max_minutes = heatmap.cells.iter().map(|c| c.raw_minutes).max().unwrap_or(0);
+
+    let normalize = |raw: u32, max: u32| if max > 0 { raw as f32 / max as f32 } else { 0.0 };
+    if heatmap.max_minutes == previous_max {
+        heatmap.cells[index].intensity = normalize(minutes, heatmap.max_minutes);
+    } else {
+        let max = heatmap.max_minutes;
+        for cell in &mut heatmap.cells {
+            cell.intensity = normalize(cell.raw_minutes, max);
+        }
+    }
+}
+
+/// Map a normalized intensity (0.0-1.0) to one of the 5 heatmap levels (0-4)
+pub fn intensity_level(intensity: f32) -> usize {
+    if intensity == 0.0 {
+        0
+    } else if intensity < 0.25 {
+        1
+    } else if intensity < 0.5 {
+        2
+    } else if intensity < 0.75 {
+        3
+    } else {
+        4
+    }
+}
+
+/// Count how many days in the heatmap window fall in each intensity level
+pub fn intensity_distribution(profile: &GrowthProfile, weeks: u8) -> [u32; 5] {
+    let mut distribution = [0u32; 5];
+
+    for cell in &generate_heatmap(profile, weeks).cells {
+        distribution[intensity_level(cell.intensity)] += 1;
+    }
+
+    distribution
+}
+
+/// Generate hourly distribution of activity (0-23 hours), bucketing session
+/// starts in local time at `utc_offset_minutes` from UTC
+pub fn generate_hourly_distribution(
+    profile: &GrowthProfile,
+    utc_offset_minutes: i32,
+) -> HashMap<u8, u64> {
     let mut hourly: HashMap<u8, u64> = HashMap::new();
 
-    for stored_session in &profile.sessions {
-        let hour = stored_session.session.started_at.hour() as u8;
+    for stored_session in profile.counted_sessions() {
+        let hour = local_time(stored_session.session.started_at, utc_offset_minutes).hour() as u8;
         *hourly.entry(hour).or_insert(0) += stored_session.session.active_time_ms;
     }
 
     hourly
 }
 
+/// Active time by session start slot: `punchcard[weekday][hour]` with
+/// weekday 0 = Monday, in local time at `utc_offset_minutes` from UTC. Starts
+/// shifted across midnight move to the neighbouring weekday.
+pub fn generate_punchcard(profile: &GrowthProfile, utc_offset_minutes: i32) -> [[u64; 24]; 7] {
+    let mut punchcard = [[0u64; 24]; 7];
+
+    for stored_session in profile.counted_sessions() {
+        let started_at = local_time(stored_session.session.started_at, utc_offset_minutes);
+        let weekday = started_at.weekday().num_days_from_monday() as usize;
+        punchcard[weekday][started_at.hour() as usize] += stored_session.session.active_time_ms;
+    }
+
+    punchcard
+}
+
+/// Wall-clock time at `utc_offset_minutes` from UTC
+fn local_time(timestamp: DateTime<Utc>, utc_offset_minutes: i32) -> DateTime<Utc> {
+    timestamp + Duration::minutes(utc_offset_minutes as i64)
+}
+
+/// How productive a weekday/hour slot has historically been, from 0.0 (no
+/// recorded activity) to 1.0 (the busiest slot on the punchcard), in the
+/// profile's local time
+pub fn productivity_forecast(profile: &GrowthProfile, hour: u8, weekday: Weekday) -> f32 {
+    if hour > 23 {
+        return 0.0;
+    }
+
+    let punchcard = generate_punchcard(profile, profile.utc_offset_minutes);
+    let max = punchcard.iter().flatten().copied().max().unwrap_or(0);
+    if max == 0 {
+        return 0.0;
+    }
+
+    let slot = punchcard[weekday.num_days_from_monday() as usize][hour as usize];
+    slot as f32 / max as f32
+}
+
+/// Active minutes per day for sessions in `project`, oldest first. Sessions
+/// without a project are only included when `project` is `"(none)"`.
+pub fn project_daily_minutes(profile: &GrowthProfile, project: &str) -> Vec<(NaiveDate, u32)> {
+    let mut daily_ms: BTreeMap<NaiveDate, u64> = BTreeMap::new();
+
+    for stored_session in profile.counted_sessions() {
+        let session = &stored_session.session;
+        let matches = match &session.project {
+            Some(p) => p == project,
+            None => project == "(none)",
+        };
+        if matches {
+            let date = local_date(session.started_at, profile.utc_offset_minutes);
+            *daily_ms.entry(date).or_insert(0) += session.active_time_ms;
+        }
+    }
+
+    daily_ms
+        .into_iter()
+        .map(|(date, ms)| (date, (ms / 1000 / 60) as u32))
+        .collect()
+}
+
 /// Generate language breakdown statistics
 pub fn generate_language_breakdown(profile: &GrowthProfile) -> Vec<LanguageStat> {
-    let total_time: u64 = profile.lifetime_stats.languages.values().sum();
+    build_language_stats(profile, &profile.lifetime_stats.languages)
+}
+
+/// Language breakdown where each day's time is weighted by
+/// `0.5^(age_days / half_life_days)`, so time `half_life_days` old counts half.
+/// A non-positive half-life disables decay.
+pub fn generate_language_breakdown_decayed(
+    profile: &GrowthProfile,
+    half_life_days: f64,
+) -> Vec<LanguageStat> {
+    let today = profile.today();
+    let mut languages: HashMap<String, u64> = HashMap::new();
+
+    for daily in &profile.daily_aggregates {
+        let age_days = (today - daily.date).num_days().max(0) as f64;
+        let weight = if half_life_days > 0.0 {
+            0.5f64.powf(age_days / half_life_days)
+        } else {
+            1.0
+        };
+        for (language, time_ms) in &daily.languages {
+            let weighted_ms = (*time_ms as f64 * weight).round() as u64;
+            *languages.entry(language.clone()).or_insert(0) += weighted_ms;
+        }
+    }
+
+    build_language_stats(profile, &languages)
+}
+
+fn build_language_stats(
+    profile: &GrowthProfile,
+    languages: &HashMap<String, u64>,
+) -> Vec<LanguageStat> {
+    let total_time: u64 = languages.values().sum();
     
     if total_time == 0 {
         return Vec::new();
     }
 
-    let mut stats: Vec<LanguageStat> = profile
-        .lifetime_stats
-        .languages
+    let mut language_files: HashMap<&str, HashSet<&str>> = HashMap::new();
+    for stored_session in profile.counted_sessions() {
+        for (file, language) in &stored_session.session.file_languages {
+            language_files.entry(language.as_str()).or_default().insert(file.as_str());
+        }
+    }
+
+    let total_files = profile
+        .counted_sessions()
+        .flat_map(|s| s.session.file_languages.keys())
+        .collect::<HashSet<_>>()
+        .len();
+
+    let mut stats: Vec<LanguageStat> = languages
         .iter()
         .map(|(language, time_ms)| {
             let percentage = (*time_ms as f32 / total_time as f32) * 100.0;
+            let files_count = language_files.get(language.as_str()).map_or(0, |f| f.len() as u32);
+            let files_percentage = if total_files > 0 {
+                files_count as f32 / total_files as f32 * 100.0
+            } else {
+                0.0
+            };
             let color = get_language_color(language);
 
             LanguageStat {
                 language: language.clone(),
                 time_ms: *time_ms,
-                files_count: 0, // TODO: Count from sessions
+                files_count,
                 percentage,
+                files_percentage,
                 color,
             }
         })
         .collect();
 
     // Sort by time (descending)
-    stats.sort_by(|a, b| b.time_ms.cmp(&a.time_ms));
+    stats.sort_by_key(|s| std::cmp::Reverse(s.time_ms));
 
     stats
 }
 
+/// Focus scores of the last N sessions in chronological order, as (session id, score)
+pub fn session_quality_trend(profile: &GrowthProfile, n: usize) -> Vec<(u64, f32)> {
+    let mut sessions: Vec<_> = profile.counted_sessions().map(|s| &s.session).collect();
+    sessions.sort_by_key(|s| s.started_at);
+
+    let skip = sessions.len().saturating_sub(n);
+    sessions
+        .into_iter()
+        .skip(skip)
+        .map(|s| (s.id, s.focus_score()))
+        .collect()
+}
+
+/// Active percentage of the last N sessions in chronological order, as
+/// (session start, active percentage 0.0-1.0)
+pub fn active_percentage_trend(profile: &GrowthProfile, n: usize) -> Vec<(DateTime<Utc>, f32)> {
+    let mut sessions: Vec<_> = profile.counted_sessions().map(|s| &s.session).collect();
+    sessions.sort_by_key(|s| s.started_at);
+
+    let skip = sessions.len().saturating_sub(n);
+    sessions
+        .into_iter()
+        .skip(skip)
+        .map(|s| (s.started_at, s.active_percentage()))
+        .collect()
+}
+
+/// Least-squares slope of the scores against their position in the series;
+/// positive means trending up
+pub fn trend_slope<T>(points: &[(T, f32)]) -> f32 {
+    let n = points.len() as f32;
+    if points.len() < 2 {
+        return 0.0;
+    }
+
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f32>() / n;
+
+    let mut numerator = 0.0;
+    let mut denominator = 0.0;
+    for (i, (_, y)) in points.iter().enumerate() {
+        let dx = i as f32 - mean_x;
+        numerator += dx * (y - mean_y);
+        denominator += dx * dx;
+    }
+
+    numerator / denominator
+}
+
+/// Top N files by time across all sessions, as (path, time_ms), descending
+pub fn top_files(profile: &GrowthProfile, n: usize) -> Vec<(String, u64)> {
+    let mut file_times: HashMap<&str, u64> = HashMap::new();
+    for stored_session in profile.counted_sessions() {
+        for (file, time) in &stored_session.session.file_times {
+            *file_times.entry(file.as_str()).or_insert(0) += time;
+        }
+    }
+
+    let mut files: Vec<(String, u64)> = file_times
+        .into_iter()
+        .map(|(file, time)| (file.to_string(), time))
+        .collect();
+    files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    files.truncate(n);
+
+    files
+}
+
 /// Get color for a language (matches CATS spec)
 fn get_language_color(language: &str) -> String {
     match language.to_lowercase().as_str() {
@@ -149,7 +408,7 @@ fn get_language_color(language: &str) -> String {
 
 /// Get daily aggregates for the last N days
 pub fn get_daily_aggregates(profile: &GrowthProfile, days: u32) -> Vec<&crate::storage::DailyAggregate> {
-    let today = Utc::now().date_naive();
+    let today = profile.today();
     let start_date = today - Duration::days(days as i64);
 
     profile
@@ -159,10 +418,123 @@ pub fn get_daily_aggregates(profile: &GrowthProfile, days: u32) -> Vec<&crate::s
         .collect()
 }
 
+/// Smallest EMA smoothing factor accepted by `daily_minutes_ema`
+const MIN_EMA_ALPHA: f32 = 0.01;
+
+/// Exponential moving average of active minutes per day over the last N days
+/// (ending today), oldest first. Inactive days count as zero before smoothing.
+/// `alpha` is clamped into (0, 1]; 1.0 returns the raw series.
+pub fn daily_minutes_ema(profile: &GrowthProfile, days: u32, alpha: f32) -> Vec<(NaiveDate, f32)> {
+    let alpha = if alpha.is_nan() { 1.0 } else { alpha.clamp(MIN_EMA_ALPHA, 1.0) };
+
+    let mut ema: Option<f32> = None;
+    daily_minutes(profile, days)
+        .into_iter()
+        .map(|(date, minutes)| {
+            let smoothed = ema.map_or(minutes, |prev| alpha * minutes + (1.0 - alpha) * prev);
+            ema = Some(smoothed);
+            (date, smoothed)
+        })
+        .collect()
+}
+
+/// Gini coefficient of active minutes per day over the last N days: 0.0 when
+/// time is spread evenly, approaching 1.0 when it all lands on one day.
+/// Windows with no activity (or fewer than two days) are 0.0.
+pub fn activity_gini(profile: &GrowthProfile, days: u32) -> f32 {
+    let mut minutes: Vec<f32> = daily_minutes(profile, days).into_iter().map(|(_, m)| m).collect();
+    let total: f32 = minutes.iter().sum();
+    if minutes.len() < 2 || total <= 0.0 {
+        return 0.0;
+    }
+
+    minutes.sort_by(|a, b| a.total_cmp(b));
+    let n = minutes.len() as f32;
+    let weighted: f32 = minutes.iter().enumerate().map(|(i, m)| (i + 1) as f32 * m).sum();
+    (2.0 * weighted / (n * total) - (n + 1.0) / n).clamp(0.0, 1.0)
+}
+
+/// Active minutes for each of the last N days ending today, oldest first,
+/// with inactive days as zero
+fn daily_minutes(profile: &GrowthProfile, days: u32) -> Vec<(NaiveDate, f32)> {
+    let today = profile.today();
+    (0..days)
+        .rev()
+        .map(|offset| {
+            let date = today - Duration::days(offset as i64);
+            let minutes = profile
+                .daily_aggregate(date)
+                .map_or(0.0, |d| d.total_time_ms as f32 / 60_000.0);
+            (date, minutes)
+        })
+        .collect()
+}
+
+/// First and last use of a language, from the daily aggregates
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageSpan {
+    pub language: String,
+    pub first_used: NaiveDate,
+    pub last_used: NaiveDate,
+    pub total_time_ms: u64,
+    pub active_days: u32,
+}
+
+/// Every language ever used with its usage span, sorted by first use
+pub fn language_usage_spans(profile: &GrowthProfile) -> Vec<LanguageSpan> {
+    let mut spans: HashMap<&str, (LanguageSpan, HashSet<NaiveDate>)> = HashMap::new();
+
+    for daily in &profile.daily_aggregates {
+        for (language, time_ms) in &daily.languages {
+            let (span, days) = spans.entry(language.as_str()).or_insert_with(|| {
+                let span = LanguageSpan {
+                    language: language.clone(),
+                    first_used: daily.date,
+                    last_used: daily.date,
+                    total_time_ms: 0,
+                    active_days: 0,
+                };
+                (span, HashSet::new())
+            });
+            span.first_used = span.first_used.min(daily.date);
+            span.last_used = span.last_used.max(daily.date);
+            span.total_time_ms += time_ms;
+            days.insert(daily.date);
+        }
+    }
+
+    let mut spans: Vec<LanguageSpan> = spans
+        .into_values()
+        .map(|(mut span, days)| {
+            span.active_days = days.len() as u32;
+            span
+        })
+        .collect();
+    spans.sort_by(|a, b| a.first_used.cmp(&b.first_used).then_with(|| a.language.cmp(&b.language)));
+
+    spans
+}
+
+/// Languages whose first appearance falls within the last N days
+pub fn new_languages_in_period(profile: &GrowthProfile, days: u32) -> Vec<String> {
+    let today = profile.today();
+    let start_date = today - Duration::days(days as i64);
+
+    let mut languages: Vec<String> = language_usage_spans(profile)
+        .into_iter()
+        .filter(|span| span.first_used >= start_date && span.first_used <= today)
+        .map(|span| span.language)
+        .collect();
+    languages.sort();
+
+    languages
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::session::Session;
+    use crate::storage::DailyAggregate;
 
     #[test]
     fn test_generate_heatmap() {
@@ -175,6 +547,36 @@ mod tests {
         assert!(heatmap.cells.len() <= 12 * 7);
     }
 
+    #[test]
+    fn test_generate_heatmap_clamps_weeks() {
+        let profile = GrowthProfile::new();
+
+        let heatmap = generate_heatmap(&profile, 0);
+        assert_eq!(heatmap.weeks, 1);
+        assert_eq!(heatmap.cells.len(), 7);
+
+        let heatmap = generate_heatmap(&profile, 53);
+        assert_eq!(heatmap.weeks, 53);
+        assert_eq!(heatmap.cells.len(), 53 * 7);
+
+        assert_eq!(generate_heatmap(&profile, 255).weeks, MAX_HEATMAP_WEEKS);
+    }
+
+    #[test]
+    fn test_intensity_distribution() {
+        let mut profile = GrowthProfile::new();
+        let today = Utc::now().date_naive();
+        for (days_ago, minutes) in [(0, 100), (1, 80), (2, 60), (3, 30), (4, 10), (5, 20)] {
+            let mut daily = crate::storage::DailyAggregate::new(today - Duration::days(days_ago));
+            daily.total_time_ms = minutes * 60 * 1000;
+            profile.daily_aggregates.push(daily);
+        }
+
+        let distribution = intensity_distribution(&profile, 2);
+        assert_eq!(distribution, [8, 2, 1, 1, 2]);
+        assert_eq!(distribution.iter().sum::<u32>(), 14);
+    }
+
     #[test]
     fn test_language_breakdown() {
         let mut profile = GrowthProfile::new();
@@ -187,6 +589,385 @@ mod tests {
         assert!(!breakdown.is_empty());
     }
 
+    #[test]
+    fn test_language_breakdown_files_count() {
+        let mut profile = GrowthProfile::new();
+        let mut session = Session::new(1);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("app.ts".to_string(), "typescript".to_string());
+        profile.add_session(session);
+        let mut session = Session::new(2);
+        session.record_file_edit("main.rs".to_string(), "rust".to_string());
+        session.record_file_edit("lib.rs".to_string(), "rust".to_string());
+        profile.add_session(session);
+
+        let breakdown = generate_language_breakdown(&profile);
+        let files = |lang: &str| breakdown.iter().find(|s| s.language == lang).unwrap().files_count;
+        assert_eq!(files("rust"), 2);
+        assert_eq!(files("typescript"), 1);
+    }
+
+    #[test]
+    fn test_language_breakdown_files_percentage() {
+        let mut profile = GrowthProfile::new();
+        let mut session = Session::new(1);
+        for _ in 0..8 {
+            session.record_file_edit("engine.rs".to_string(), "rust".to_string());
+        }
+        for file in ["a.ts", "b.ts", "c.ts"] {
+            session.record_file_edit(file.to_string(), "typescript".to_string());
+        }
+        profile.add_session(session);
+
+        let breakdown = generate_language_breakdown(&profile);
+        let rust = breakdown.iter().find(|s| s.language == "rust").unwrap();
+        let typescript = breakdown.iter().find(|s| s.language == "typescript").unwrap();
+        assert!(rust.percentage > typescript.percentage);
+        assert!(rust.files_percentage < typescript.files_percentage);
+        assert!((rust.files_percentage - 25.0).abs() < 0.01);
+        assert!((typescript.files_percentage - 75.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_non_counting_sessions_excluded() {
+        let mut profile = GrowthProfile::new();
+        let mut counted = Session::new(1);
+        counted.active_time_ms = 60_000;
+        counted.record_file_edit("engine.rs".to_string(), "rust".to_string());
+        profile.add_session(counted);
+
+        let mut demo = Session::new(2);
+        demo.active_time_ms = 30 * 60_000;
+        demo.counts_toward_stats = false;
+        for file in ["a.ts", "b.ts", "c.ts"] {
+            demo.record_file_edit(file.to_string(), "typescript".to_string());
+        }
+        profile.add_session(demo);
+
+        let breakdown = generate_language_breakdown(&profile);
+        let rust = breakdown.iter().find(|s| s.language == "rust").unwrap();
+        assert!((rust.files_percentage - 100.0).abs() < 0.01);
+
+        let top = top_files(&profile, 10);
+        assert_eq!(top.len(), 1);
+        assert_eq!(top[0].0, "engine.rs");
+
+        let hourly_total: u64 = generate_hourly_distribution(&profile, 0).values().sum();
+        assert_eq!(hourly_total, 60_000);
+        let punchcard_total: u64 = generate_punchcard(&profile, 0).iter().flatten().sum();
+        assert_eq!(punchcard_total, 60_000);
+    }
+
+    #[test]
+    fn test_hourly_distribution_local_offset() {
+        let mut profile = GrowthProfile::new();
+        // Tuesday 2024-01-02 23:50 UTC
+        let start = NaiveDate::from_ymd_opt(2024, 1, 2)
+            .unwrap()
+            .and_hms_opt(23, 50, 0)
+            .unwrap()
+            .and_utc();
+        let mut session = Session::new_at(1, start);
+        session.active_time_ms = 60_000;
+        profile.add_session(session);
+
+        let utc = generate_hourly_distribution(&profile, 0);
+        assert_eq!(utc.get(&23), Some(&60_000));
+
+        // +00:30 wraps past midnight into Wednesday
+        let ahead = generate_hourly_distribution(&profile, 30);
+        assert_eq!(ahead.get(&0), Some(&60_000));
+        assert_eq!(generate_punchcard(&profile, 30)[2][0], 60_000);
+
+        // -05:30 lands in the evening of the same day
+        let behind = generate_hourly_distribution(&profile, -330);
+        assert_eq!(behind.get(&18), Some(&60_000));
+        assert_eq!(generate_punchcard(&profile, -330)[1][18], 60_000);
+    }
+
+    #[test]
+    fn test_productivity_forecast() {
+        let mut profile = GrowthProfile::new();
+        assert_eq!(productivity_forecast(&profile, 9, Weekday::Tue), 0.0);
+
+        // Tuesday 2024-01-02: a long 09:00 session and a short 21:00 session
+        for (id, hour, minutes) in [(1, 9, 120), (2, 9, 60), (3, 21, 30)] {
+            let start = NaiveDate::from_ymd_opt(2024, 1, 2)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap()
+                .and_utc();
+            let mut session = Session::new_at(id, start);
+            session.active_time_ms = minutes * 60 * 1000;
+            profile.add_session(session);
+        }
+
+        let busy = productivity_forecast(&profile, 9, Weekday::Tue);
+        let quiet = productivity_forecast(&profile, 21, Weekday::Tue);
+        let dead = productivity_forecast(&profile, 9, Weekday::Sun);
+        assert_eq!(busy, 1.0);
+        assert!((quiet - 30.0 / 180.0).abs() < 0.001);
+        assert_eq!(dead, 0.0);
+        assert_eq!(productivity_forecast(&profile, 24, Weekday::Tue), 0.0);
+
+        // At -10:00 the 09:00 UTC sessions fall at 23:00 the previous evening
+        profile.utc_offset_minutes = -600;
+        assert_eq!(productivity_forecast(&profile, 23, Weekday::Mon), 1.0);
+        assert_eq!(productivity_forecast(&profile, 9, Weekday::Tue), 0.0);
+    }
+
+    #[test]
+    fn test_project_daily_minutes() {
+        let mut profile = GrowthProfile::new();
+        let day = |d| NaiveDate::from_ymd_opt(2024, 6, d).unwrap();
+        let sessions = [
+            (1, Some("dendrite"), 3, 30),
+            (2, Some("dendrite"), 3, 15),
+            (3, Some("website"), 3, 60),
+            (4, Some("dendrite"), 5, 10),
+            (5, None, 4, 20),
+        ];
+        for (id, project, d, minutes) in sessions {
+            let mut session = Session::new_at(id, day(d).and_hms_opt(10, 0, 0).unwrap().and_utc());
+            session.project = project.map(str::to_string);
+            session.active_time_ms = minutes * 60 * 1000;
+            profile.add_session(session);
+        }
+
+        assert_eq!(project_daily_minutes(&profile, "dendrite"), vec![(day(3), 45), (day(5), 10)]);
+        assert_eq!(project_daily_minutes(&profile, "website"), vec![(day(3), 60)]);
+        assert_eq!(project_daily_minutes(&profile, "(none)"), vec![(day(4), 20)]);
+
+        let mut demo = Session::new_at(6, day(5).and_hms_opt(12, 0, 0).unwrap().and_utc());
+        demo.project = Some("website".to_string());
+        demo.active_time_ms = 90 * 60 * 1000;
+        demo.counts_toward_stats = false;
+        profile.add_session(demo);
+        assert_eq!(project_daily_minutes(&profile, "website"), vec![(day(3), 60)]);
+        assert!(project_daily_minutes(&profile, "unknown").is_empty());
+    }
+
+    #[test]
+    fn test_update_heatmap_cell_matches_regeneration() {
+        let today = Utc::now().date_naive();
+        let mut profile = GrowthProfile::new();
+        for (days_ago, minutes) in [(1, 60), (5, 30)] {
+            let mut daily = DailyAggregate::new(today - Duration::days(days_ago));
+            daily.total_time_ms = minutes * 60 * 1000;
+            profile.daily_aggregates.push(daily);
+        }
+        let mut heatmap = generate_heatmap(&profile, 2);
+        let original = heatmap.clone();
+
+        // Below the current max: only the updated cell changes
+        update_heatmap_cell(&mut heatmap, today - Duration::days(3), 15, today);
+        let changed: Vec<_> = heatmap
+            .cells
+            .iter()
+            .zip(&original.cells)
+            .filter(|(a, b)| a.raw_minutes != b.raw_minutes || a.intensity != b.intensity)
+            .map(|(a, _)| a.date)
+            .collect();
+        assert_eq!(changed, vec![today - Duration::days(3)]);
+
+        // A new max renormalizes every cell, matching a full regeneration
+        update_heatmap_cell(&mut heatmap, today, 120, today);
+        for (days_ago, minutes) in [(3, 15), (0, 120)] {
+            let mut daily = DailyAggregate::new(today - Duration::days(days_ago));
+            daily.total_time_ms = minutes * 60 * 1000;
+            profile.daily_aggregates.push(daily);
+        }
+        let regenerated = generate_heatmap(&profile, 2);
+        assert_eq!(heatmap.max_minutes, regenerated.max_minutes);
+        assert_eq!(heatmap.total_minutes, regenerated.total_minutes);
+        for (a, b) in heatmap.cells.iter().zip(&regenerated.cells) {
+            assert_eq!((a.date, a.raw_minutes), (b.date, b.raw_minutes));
+            assert!((a.intensity - b.intensity).abs() < f32::EPSILON);
+        }
+
+        // Out of range dates are ignored
+        let before = heatmap.total_minutes;
+        update_heatmap_cell(&mut heatmap, today - Duration::days(60), 999, today);
+        update_heatmap_cell(&mut heatmap, today + Duration::days(1), 999, today);
+        assert_eq!(heatmap.total_minutes, before);
+    }
+
+    #[test]
+    fn test_daily_minutes_ema_smooths_step() {
+        let today = Utc::now().date_naive();
+        let mut profile = GrowthProfile::new();
+        for days_ago in 0..5 {
+            let mut daily = DailyAggregate::new(today - Duration::days(days_ago));
+            daily.total_time_ms = 60 * 60 * 1000;
+            profile.daily_aggregates.push(daily);
+        }
+
+        let series = daily_minutes_ema(&profile, 10, 0.5);
+        assert_eq!(series.len(), 10);
+        assert_eq!(series[9].0, today);
+        assert!(series[..5].iter().all(|(_, m)| *m == 0.0));
+
+        // Lags behind the step and climbs toward it without overshooting
+        let after: Vec<f32> = series[5..].iter().map(|(_, m)| *m).collect();
+        assert!((after[0] - 30.0).abs() < 1e-3);
+        assert!(after.windows(2).all(|w| w[0] < w[1]));
+        assert!(after.iter().all(|m| *m < 60.0));
+
+        // Out-of-range alpha is clamped: above 1 means no smoothing
+        let raw = daily_minutes_ema(&profile, 10, 5.0);
+        assert_eq!(raw[5].1, 60.0);
+    }
+
+    #[test]
+    fn test_activity_gini() {
+        let today = Utc::now().date_naive();
+        let profile_with = |days: &[(i64, u64)]| {
+            let mut profile = GrowthProfile::new();
+            for (days_ago, minutes) in days {
+                let mut daily = DailyAggregate::new(today - Duration::days(*days_ago));
+                daily.total_time_ms = minutes * 60 * 1000;
+                profile.daily_aggregates.push(daily);
+            }
+            profile
+        };
+
+        let even: Vec<(i64, u64)> = (0..7).map(|d| (d, 60)).collect();
+        let even_gini = activity_gini(&profile_with(&even), 7);
+        let spike_gini = activity_gini(&profile_with(&[(3, 420)]), 7);
+
+        assert!(even_gini < 0.01);
+        assert!(spike_gini > 0.8);
+        assert!(spike_gini < 1.0);
+        assert_eq!(activity_gini(&GrowthProfile::new(), 7), 0.0);
+        assert_eq!(activity_gini(&profile_with(&[(0, 30)]), 1), 0.0);
+    }
+
+    #[test]
+    fn test_language_usage_spans() {
+        let mut profile = GrowthProfile::new();
+        let day = |d| NaiveDate::from_ymd_opt(2024, 2, d).unwrap();
+        let usage = [(1, vec!["python"]), (3, vec!["python", "rust"]), (9, vec!["rust"])];
+        for (d, languages) in usage {
+            let mut daily = DailyAggregate::new(day(d));
+            for language in languages {
+                daily.languages.insert(language.to_string(), 1000);
+            }
+            profile.daily_aggregates.push(daily);
+        }
+
+        let spans = language_usage_spans(&profile);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[0].language, "python");
+        assert_eq!((spans[0].first_used, spans[0].last_used), (day(1), day(3)));
+        assert_eq!((spans[0].total_time_ms, spans[0].active_days), (2000, 2));
+        assert_eq!(spans[1].language, "rust");
+        assert_eq!((spans[1].first_used, spans[1].last_used), (day(3), day(9)));
+        assert_eq!((spans[1].total_time_ms, spans[1].active_days), (2000, 2));
+    }
+
+    #[test]
+    fn test_new_languages_in_period() {
+        let mut profile = GrowthProfile::new();
+        let today = Utc::now().date_naive();
+
+        let mut old = crate::storage::DailyAggregate::new(today - Duration::days(60));
+        old.languages.insert("rust".to_string(), 1000);
+        let mut recent = crate::storage::DailyAggregate::new(today - Duration::days(3));
+        recent.languages.insert("rust".to_string(), 1000);
+        recent.languages.insert("go".to_string(), 1000);
+        profile.daily_aggregates.push(recent);
+        profile.daily_aggregates.push(old);
+
+        assert_eq!(new_languages_in_period(&profile, 30), vec!["go".to_string()]);
+    }
+
+    #[test]
+    fn test_session_quality_trend() {
+        let mut profile = GrowthProfile::new();
+        let now = Utc::now();
+        for i in 0..4u64 {
+            let mut session = Session::new(i + 1);
+            session.started_at = now - Duration::hours(10 - i as i64);
+            session.ended_at = Some(session.started_at + Duration::minutes(10));
+            session.active_time_ms = (i + 2) * 60 * 1000;
+            profile.add_session(session);
+        }
+
+        let trend = session_quality_trend(&profile, 3);
+        let ids: Vec<u64> = trend.iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![2, 3, 4]);
+        assert!(trend_slope(&trend) > 0.0);
+        assert_eq!(trend_slope(&trend[..1]), 0.0);
+    }
+
+    #[test]
+    fn test_session_quality_trend_skips_non_counting() {
+        let mut profile = GrowthProfile::new();
+        profile.add_session(Session::new(1));
+        let mut demo = Session::new(2);
+        demo.counts_toward_stats = false;
+        profile.add_session(demo);
+
+        let ids: Vec<u64> = session_quality_trend(&profile, 5).iter().map(|(id, _)| *id).collect();
+        assert_eq!(ids, vec![1]);
+    }
+
+    #[test]
+    fn test_language_breakdown_decayed_favors_recent() {
+        let mut profile = GrowthProfile::new();
+        let today = Utc::now().date_naive();
+
+        let mut old = DailyAggregate::new(today - Duration::days(120));
+        old.languages.insert("python".to_string(), 10 * 3_600_000);
+        let mut recent = DailyAggregate::new(today - Duration::days(1));
+        recent.languages.insert("rust".to_string(), 2 * 3_600_000);
+        profile.daily_aggregates = vec![old, recent];
+
+        let decayed = generate_language_breakdown_decayed(&profile, 30.0);
+        assert_eq!(decayed[0].language, "rust");
+        assert_eq!(decayed[1].language, "python");
+        // 120 days is four half-lives: 10h / 16
+        assert_eq!(decayed[1].time_ms, 10 * 3_600_000 / 16);
+
+        let undecayed = generate_language_breakdown_decayed(&profile, 0.0);
+        assert_eq!(undecayed[0].language, "python");
+    }
+
+    #[test]
+    fn test_active_percentage_trend() {
+        let mut profile = GrowthProfile::new();
+        let now = Utc::now();
+        for i in 0..5u64 {
+            let mut session = Session::new(i + 1);
+            session.started_at = now - Duration::days(5 - i as i64);
+            session.ended_at = Some(session.started_at + Duration::minutes(60));
+            session.active_time_ms = (50 - i * 10) * 60 * 1000;
+            profile.add_session(session);
+        }
+
+        let trend = active_percentage_trend(&profile, 4);
+        assert_eq!(trend.len(), 4);
+        assert!(trend.windows(2).all(|w| w[0].0 < w[1].0));
+        assert!(trend_slope(&trend) < 0.0);
+    }
+
+    #[test]
+    fn test_active_percentage_trend_skips_non_counting() {
+        let mut profile = GrowthProfile::new();
+        let counted = Session::new(1);
+        let started_at = counted.started_at;
+        profile.add_session(counted);
+        let mut demo = Session::new(2);
+        demo.started_at = started_at + Duration::minutes(5);
+        demo.counts_toward_stats = false;
+        profile.add_session(demo);
+
+        let trend = active_percentage_trend(&profile, 5);
+        assert_eq!(trend.len(), 1);
+        assert_eq!(trend[0].0, started_at);
+    }
+
     #[test]
     fn test_language_colors() {
         assert_eq!(get_language_color("rust"), "#dea584");